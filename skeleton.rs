@@ -1,27 +1,29 @@
+use crate::parse::ParseError;
+
 #[allow(dead_code)]
-pub fn one(input: &str) -> u32 {
+pub fn one(input: &str) -> Result<u32, ParseError> {
     unimplemented!()
 }
 
 #[allow(dead_code)]
-pub fn two(input: &str) -> u32 {
+pub fn two(input: &str) -> Result<u32, ParseError> {
     unimplemented!()
 }
 
 #[cfg(test)]
 mod tests {
-    const INPUT: &str = "REPLACEME";
+    use crate::inputs::real;
 
     #[test]
     fn one() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(0, super::one(&input));
+        let input = real(REPLACEME);
+        assert_eq!(Ok(0), super::one(&input));
     }
 
     #[test]
     #[ignore]
     fn two() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(0, super::two(&input));
+        let input = real(REPLACEME);
+        assert_eq!(Ok(0), super::two(&input));
     }
 }
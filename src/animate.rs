@@ -0,0 +1,70 @@
+use crate::{
+    days,
+    inputs::{Input, Inputs},
+    utils::render::{Recorder, RenderOptions},
+};
+
+pub const USAGE: &str =
+    "usage: aoc-2022 animate <day> <part> <OUT.gif | OUT_DIR> [--every N] [--scale N] [--delay CS] [--example [N]]";
+
+/// Runs a part of a simulation day and saves its frames to a GIF, or to a directory of PPM images.
+pub fn command(args: &[String]) -> Result<(), String> {
+    let usage = || USAGE.to_string();
+    let (day, part, out, rest) = match args {
+        [day, part, out, rest @ ..] => (day, part, out, rest),
+        _ => return Err(usage()),
+    };
+    let day = day.parse::<u8>().map_err(|_| usage())?;
+    let part = part.parse::<usize>().map_err(|_| usage())?;
+
+    let mut every = 1;
+    let mut delay = 10;
+    let mut options = RenderOptions {
+        scale: 4,
+        ..Default::default()
+    };
+    let mut input = Input::Real;
+
+    let mut args = rest.iter().peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--every" => every = parse_value(args.next())?,
+            "--scale" => options.scale = parse_value(args.next())?,
+            "--delay" => delay = parse_value(args.next())?,
+            "--example" => {
+                let n = match args.peek() {
+                    Some(n) if !n.starts_with("--") => parse_value(args.next())?,
+                    _ => 1,
+                };
+                input = Input::Example(n);
+            }
+            _ => return Err(usage()),
+        }
+    }
+
+    let solution = days::get(day).ok_or_else(|| format!("day {} is not implemented", day))?;
+    let record = solution
+        .record
+        .ok_or_else(|| format!("day {} can't be animated", day))?;
+    if solution.part(part).is_none() {
+        return Err(format!("day {} has no part {}", day, part));
+    }
+
+    let input = Inputs::from_env()
+        .read(day, input)
+        .map_err(|e| e.to_string())?;
+    let mut recorder = Recorder::new().every(every).delay(delay);
+    record(&input, part, &mut recorder).map_err(|e| e.to_string())?;
+
+    recorder
+        .save(out, &options)
+        .map_err(|e| format!("failed to write `{}`: {}", out, e))?;
+    println!("wrote {} frames to {}", recorder.frame_count(), out);
+    Ok(())
+}
+
+fn parse_value<T: std::str::FromStr>(value: Option<&String>) -> Result<T, String> {
+    value
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| USAGE.to_string())
+}
@@ -0,0 +1,108 @@
+use std::fmt::Display;
+
+/// The result of solving a puzzle part, regardless of which type the day computes it in.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Answer {
+    Integer(i64),
+    BigInteger(i128),
+    Text(String),
+    /// Pixel art such as a CRT screen, one string per row.
+    Glyphs(Vec<String>),
+}
+
+impl Answer {
+    /// Splits a flat string of pixels into rows of `width` pixels each.
+    pub fn glyphs(pixels: &str, width: usize) -> Self {
+        let chars = pixels.chars().collect::<Vec<_>>();
+        Answer::Glyphs(
+            chars
+                .chunks(width)
+                .map(|row| row.iter().collect())
+                .collect(),
+        )
+    }
+}
+
+macro_rules! impl_from_small_int {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(v: $t) -> Self {
+                    Answer::Integer(v.into())
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_from_big_int {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(v: $t) -> Self {
+                    match i64::try_from(v) {
+                        Ok(v) => Answer::Integer(v),
+                        Err(_) => Answer::BigInteger(v as i128),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_small_int!(i32, u32, i64);
+impl_from_big_int!(u64, usize, i128);
+
+impl From<u128> for Answer {
+    fn from(v: u128) -> Self {
+        match i128::try_from(v) {
+            Ok(v) => v.into(),
+            Err(_) => Answer::Text(v.to_string()),
+        }
+    }
+}
+
+impl From<String> for Answer {
+    fn from(v: String) -> Self {
+        Answer::Text(v)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(v: &str) -> Self {
+        Answer::Text(v.to_owned())
+    }
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Answer::Integer(v) => write!(f, "{}", v),
+            Answer::BigInteger(v) => write!(f, "{}", v),
+            Answer::Text(s) => write!(f, "{}", s),
+            Answer::Glyphs(rows) => write!(f, "{}", rows.join("\n")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Answer;
+
+    #[test]
+    fn from_ints() {
+        assert_eq!(Answer::Integer(42), 42u32.into());
+        assert_eq!(Answer::Integer(-42), (-42i64).into());
+        assert_eq!(Answer::Integer(24389045529), 24389045529u64.into());
+        assert_eq!(Answer::BigInteger(u64::MAX as i128), u64::MAX.into());
+        assert_eq!(Answer::Integer(42), 42u128.into());
+        assert_eq!(Answer::Text(u128::MAX.to_string()), u128::MAX.into());
+    }
+
+    #[test]
+    fn glyphs() {
+        let answer = Answer::glyphs("#..##..#", 4);
+        assert_eq!(Answer::Glyphs(vec!["#..#".into(), "#..#".into()]), answer);
+        assert_eq!("#..#\n#..#", answer.to_string());
+    }
+}
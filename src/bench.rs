@@ -0,0 +1,224 @@
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    days::{self, Solution},
+    inputs::{Input, Inputs},
+};
+
+pub const USAGE: &str =
+    "usage: aoc-2022 bench [--runs N] [--day DAY] [--example] [--save PATH] [--baseline PATH]";
+
+/// Min, median and max of a series of runs, in nanoseconds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timing {
+    pub min: u64,
+    pub median: u64,
+    pub max: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DayTiming {
+    pub day: u8,
+    pub parse: Option<Timing>,
+    /// Time spent solving each part. If the day has a separate parse step, its time is not
+    /// included here.
+    pub parts: Vec<Timing>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Report {
+    pub runs: usize,
+    pub days: Vec<DayTiming>,
+}
+
+impl Timing {
+    fn from_samples(mut samples: Vec<Duration>) -> Self {
+        samples.sort();
+        let nanos = |d: &Duration| d.as_nanos() as u64;
+        Timing {
+            min: nanos(&samples[0]),
+            median: nanos(&samples[samples.len() / 2]),
+            max: nanos(&samples[samples.len() - 1]),
+        }
+    }
+}
+
+fn time<T>(f: impl FnOnce() -> T) -> Duration {
+    let start = Instant::now();
+    black_box(f());
+    start.elapsed()
+}
+
+/// Runs every part of `solution` `runs` times on `input`.
+///
+/// Parts always parse their own input, so a part's solve time is its total run time minus the
+/// parse time measured in the same run.
+pub fn bench_day(solution: &Solution, input: &str, runs: usize) -> DayTiming {
+    let mut parse_samples = Vec::with_capacity(runs);
+    let mut part_samples = vec![Vec::with_capacity(runs); solution.parts.len()];
+
+    for _ in 0..runs.max(1) {
+        let parse_time = solution
+            .parse
+            .map(|parse| time(|| parse(input).ok()))
+            .unwrap_or_default();
+        parse_samples.push(parse_time);
+
+        for (part, samples) in solution.parts.iter().zip(part_samples.iter_mut()) {
+            let total = time(|| part(input).ok());
+            samples.push(total.saturating_sub(parse_time));
+        }
+    }
+
+    DayTiming {
+        day: solution.day,
+        parse: solution.parse.map(|_| Timing::from_samples(parse_samples)),
+        parts: part_samples.into_iter().map(Timing::from_samples).collect(),
+    }
+}
+
+impl Report {
+    pub fn print(&self, baseline: Option<&Report>) {
+        println!(
+            "{:>3}  {:<6}  {:>10}  {:>10}  {:>10}  {:>9}",
+            "day", "phase", "min", "median", "max", "baseline"
+        );
+
+        for day in &self.days {
+            let base = baseline.and_then(|b| b.days.iter().find(|d| d.day == day.day));
+
+            let mut phases = vec![("parse".to_string(), day.parse, base.and_then(|b| b.parse))];
+            for (i, timing) in day.parts.iter().enumerate() {
+                let base_timing = base.and_then(|b| b.parts.get(i)).copied();
+                phases.push((format!("part {}", i + 1), Some(*timing), base_timing));
+            }
+
+            for (phase, timing, base_timing) in phases {
+                let timing = match timing {
+                    Some(t) => t,
+                    None => continue,
+                };
+                let change = match base_timing {
+                    Some(b) if b.median > 0 => format!(
+                        "{:+.1}%",
+                        (timing.median as f64 / b.median as f64 - 1.0) * 100.0
+                    ),
+                    _ => "-".to_string(),
+                };
+                println!(
+                    "{:>3}  {:<6}  {:>10}  {:>10}  {:>10}  {:>9}",
+                    day.day,
+                    phase,
+                    fmt_nanos(timing.min),
+                    fmt_nanos(timing.median),
+                    fmt_nanos(timing.max),
+                    change
+                );
+            }
+        }
+    }
+}
+
+fn fmt_nanos(nanos: u64) -> String {
+    match nanos {
+        n if n < 1_000 => format!("{}ns", n),
+        n if n < 1_000_000 => format!("{:.2}µs", n as f64 / 1e3),
+        n if n < 1_000_000_000 => format!("{:.2}ms", n as f64 / 1e6),
+        n => format!("{:.2}s", n as f64 / 1e9),
+    }
+}
+
+/// Entry point for the `bench` subcommand.
+pub fn command(args: &[String]) -> Result<(), String> {
+    let mut runs = 10;
+    let mut day = None;
+    let mut example = false;
+    let mut save = None;
+    let mut baseline = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| USAGE.to_string());
+        match arg.as_str() {
+            "--runs" => runs = value()?.parse().map_err(|_| USAGE.to_string())?,
+            "--day" => day = Some(value()?.parse::<u8>().map_err(|_| USAGE.to_string())?),
+            "--example" => example = true,
+            "--save" => save = Some(value()?.clone()),
+            "--baseline" => baseline = Some(value()?.clone()),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    let baseline = baseline
+        .map(|path| {
+            let json = std::fs::read_to_string(&path)
+                .map_err(|e| format!("failed to read baseline `{}`: {}", path, e))?;
+            serde_json::from_str::<Report>(&json)
+                .map_err(|e| format!("invalid baseline `{}`: {}", path, e))
+        })
+        .transpose()?;
+
+    let inputs = Inputs::from_env();
+    let mut report = Report {
+        runs,
+        days: Vec::new(),
+    };
+    for solution in days::SOLUTIONS
+        .iter()
+        .filter(|s| day.is_none_or(|d| d == s.day))
+    {
+        let input = if example {
+            Input::Example(1)
+        } else {
+            Input::Real
+        };
+        // a warm-up run of every part, which also makes sure the input can be parsed
+        let checked = inputs
+            .read(solution.day, input)
+            .map_err(|e| e.to_string())
+            .and_then(|input| {
+                for part in solution.parts {
+                    part(&input).map_err(|e| e.to_string())?;
+                }
+                Ok(input)
+            });
+        match checked {
+            Ok(input) => report.days.push(bench_day(solution, &input, runs)),
+            Err(e) => eprintln!("skipping day {}: {}", solution.day, e),
+        }
+    }
+
+    report.print(baseline.as_ref());
+
+    if let Some(path) = save {
+        let json = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+        std::fs::write(&path, json).map_err(|e| format!("failed to write `{}`: {}", path, e))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Timing;
+
+    #[test]
+    fn timing_from_samples() {
+        let samples = [5, 1, 4, 2, 3].map(Duration::from_nanos).to_vec();
+        assert_eq!(
+            Timing {
+                min: 1,
+                median: 3,
+                max: 5
+            },
+            Timing::from_samples(samples)
+        );
+    }
+}
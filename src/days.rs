@@ -13,6 +13,7 @@ pub mod day12;
 pub mod day13;
 pub mod day14;
 pub mod day15;
+pub mod day16;
 pub mod day17;
 pub mod day18;
 pub mod day19;
@@ -22,3 +23,153 @@ pub mod day22;
 pub mod day23;
 pub mod day24;
 pub mod day25;
+
+use std::fmt::Display;
+
+use crate::{answer::Answer, ocr, parse::ParseError, utils::render::Recorder};
+
+/// A single day's puzzle, with one entry in `parts` per puzzle part.
+///
+/// `parse`, if present, runs only the day's input parsing so that it can be timed separately from
+/// solving. `record`, if present, runs a part of a simulation day while recording its frames.
+pub struct Solution {
+    pub day: u8,
+    pub parse: Option<Parse>,
+    pub parts: &'static [Part],
+    pub record: Option<Record>,
+}
+
+pub type Parse = fn(&str) -> Result<(), ParseError>;
+pub type Part = fn(&str) -> Result<Answer, SolveError>;
+pub type Record = fn(&str, usize, &mut Recorder) -> Result<(), SolveError>;
+
+/// Why a part has no answer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SolveError {
+    /// The input doesn't look the way the puzzle says it should.
+    Parse(ParseError),
+    /// The input looks right, but the puzzle can't be solved for it.
+    Unsolvable { day: u8, reason: String },
+}
+
+impl SolveError {
+    pub fn unsolvable(day: u8, reason: impl Into<String>) -> Self {
+        SolveError::Unsolvable {
+            day,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl From<ParseError> for SolveError {
+    fn from(e: ParseError) -> Self {
+        SolveError::Parse(e)
+    }
+}
+
+impl Display for SolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolveError::Parse(e) => write!(f, "{}", e),
+            SolveError::Unsolvable { day, reason } => write!(f, "day {}: {}", day, reason),
+        }
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+impl Solution {
+    pub fn part(&self, part: usize) -> Option<Part> {
+        part.checked_sub(1).and_then(|i| self.parts.get(i)).copied()
+    }
+}
+
+macro_rules! solution {
+    ($day:literal, $module:ident, parse = $parse:ident, [$($part:ident),+]) => {
+        Solution {
+            day: $day,
+            parse: Some(|input| {
+                std::hint::black_box($module::$parse(input)?);
+                Ok(())
+            }),
+            parts: &[$(|input| $module::$part(input).map(Into::into).map_err(Into::into)),+],
+            record: None,
+        }
+    };
+    ($day:literal, $module:ident, [$($part:ident),+]) => {
+        Solution {
+            day: $day,
+            parse: None,
+            parts: &[$(|input| $module::$part(input).map(Into::into).map_err(Into::into)),+],
+            record: None,
+        }
+    };
+    ($day:literal, $module:ident $(, parse = $parse:ident)?) => {
+        solution!($day, $module $(, parse = $parse)?, [one, two])
+    };
+}
+
+pub const SOLUTIONS: &[Solution] = &[
+    solution!(1, day01),
+    solution!(2, day02, parse = rounds),
+    solution!(3, day03),
+    solution!(4, day04),
+    solution!(5, day05, parse = parse_input),
+    solution!(6, day06),
+    solution!(7, day07, parse = parse_files),
+    solution!(8, day08, parse = parse_grid),
+    Solution {
+        record: Some(day09::record),
+        ..solution!(9, day09, parse = parse_moves)
+    },
+    Solution {
+        day: 10,
+        parse: Some(|input| {
+            std::hint::black_box(day10::parse(input)?);
+            Ok(())
+        }),
+        parts: &[
+            |input| day10::one(input).map(Into::into).map_err(Into::into),
+            |input| {
+                day10::two(input)
+                    .map(|pixels| {
+                        let answer = Answer::glyphs(&pixels, 40);
+                        match &answer {
+                            Answer::Glyphs(rows) => ocr::read(rows).map_or(answer, Answer::Text),
+                            _ => answer,
+                        }
+                    })
+                    .map_err(Into::into)
+            },
+        ],
+        record: None,
+    },
+    solution!(11, day11, parse = parse_monkeys),
+    solution!(12, day12, parse = parse),
+    solution!(13, day13, parse = parse_input),
+    Solution {
+        record: Some(day14::record),
+        ..solution!(14, day14, parse = parse)
+    },
+    solution!(15, day15, parse = parse),
+    solution!(16, day16, parse = parse),
+    solution!(17, day17, parse = parse),
+    solution!(18, day18, parse = parse_input),
+    solution!(19, day19, parse = parse),
+    solution!(20, day20, parse = parse),
+    solution!(21, day21, parse = parse),
+    solution!(22, day22, parse = parse),
+    Solution {
+        record: Some(day23::record),
+        ..solution!(23, day23, parse = parse)
+    },
+    Solution {
+        record: Some(day24::record),
+        ..solution!(24, day24, parse = parse)
+    },
+    solution!(25, day25, [one]),
+];
+
+pub fn get(day: u8) -> Option<&'static Solution> {
+    SOLUTIONS.iter().find(|s| s.day == day)
+}
@@ -1,39 +1,58 @@
-pub fn sums(input: &str) -> Vec<u32> {
-    dbg!(input);
-    let blocks: Vec<Vec<u32>> = input
-        .replace("\r\n", "\n")
-        .split("\n\n")
-        .map(|b| b.lines().map(|l| l.parse().unwrap()).collect())
-        .collect();
+use crate::parse::{self, ParseError};
+
+pub fn sums(input: &str) -> Result<Vec<u32>, ParseError> {
+    // empty lines separate the elves
+    let calories = parse::lines(1, input, "a number or an empty line", |l| {
+        if l.is_empty() {
+            Some(None)
+        } else {
+            l.parse::<u32>().ok().map(Some)
+        }
+    })?;
 
-    let mut sums: Vec<_> = blocks.iter().map(|b| b.iter().sum::<u32>()).collect();
+    let mut sums: Vec<_> = calories
+        .split(|c| c.is_none())
+        .map(|b| b.iter().flatten().sum::<u32>())
+        .collect();
     sums.sort();
-    sums
+    Ok(sums)
 }
 
 #[allow(dead_code)]
-pub fn one(input: &str) -> u32 {
-    *sums(input).iter().rev().next().unwrap()
+pub fn one(input: &str) -> Result<u32, ParseError> {
+    Ok(*sums(input)?.iter().next_back().unwrap())
 }
 
 #[allow(dead_code)]
-pub fn two(input: &str) -> u32 {
-    sums(input).iter().rev().take(3).sum()
+pub fn two(input: &str) -> Result<u32, ParseError> {
+    Ok(sums(input)?.iter().rev().take(3).sum())
 }
 
 #[cfg(test)]
 mod tests {
-    const INPUT: &str = "src/input/01.txt";
+    use crate::inputs::{example, real};
 
     #[test]
     fn one() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(69281, super::one(&input));
+        let input = real(1);
+        assert_eq!(Ok(69281), super::one(&input));
     }
 
     #[test]
     fn two() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(201524, super::two(&input));
+        let input = real(1);
+        assert_eq!(Ok(201524), super::two(&input));
+    }
+
+    #[test]
+    fn one_example() {
+        let input = example(1, 1);
+        assert_eq!(Ok(24000), super::one(&input));
+    }
+
+    #[test]
+    fn two_example() {
+        let input = example(1, 1);
+        assert_eq!(Ok(45000), super::two(&input));
     }
 }
@@ -1,29 +1,28 @@
 use scan_fmt::scan_fmt;
 
+use crate::parse::{self, ParseError};
+
 #[allow(dead_code)]
-pub fn one(input: &str) -> u32 {
-    rounds(input)
+pub fn one(input: &str) -> Result<u32, ParseError> {
+    Ok(rounds(input)?
         .iter()
         .map(|(opponent, mine)| score_guide(opponent, mine))
-        .sum()
+        .sum())
 }
 
 #[allow(dead_code)]
-pub fn two(input: &str) -> u32 {
-    rounds(input)
+pub fn two(input: &str) -> Result<u32, ParseError> {
+    Ok(rounds(input)?
         .iter()
         .map(|(opponent, mine)| score_to_win(opponent, mine))
-        .sum()
+        .sum())
 }
 
-fn rounds(input: &str) -> Vec<(Hand, Hand)> {
-    input
-        .lines()
-        .map(|l| {
-            let (a, b) = scan_fmt!(l, "{} {}", char, char).unwrap();
-            (Hand::parse(&a), Hand::parse(&b))
-        })
-        .collect()
+pub(crate) fn rounds(input: &str) -> Result<Vec<(Hand, Hand)>, ParseError> {
+    parse::lines(2, input, "two hands like `A Y`", |l| {
+        let (a, b) = scan_fmt!(l, "{} {}", char, char).ok()?;
+        Some((Hand::parse(&a)?, Hand::parse(&b)?))
+    })
 }
 
 fn score_guide(opponent: &Hand, mine: &Hand) -> u32 {
@@ -59,7 +58,7 @@ fn score_to_win(opponent: &Hand, mine: &Hand) -> u32 {
 }
 
 #[derive(Copy, Clone, Debug)]
-enum Hand {
+pub(crate) enum Hand {
     Rock,
     Paper,
     Scissors,
@@ -73,12 +72,12 @@ enum NeedsTo {
 }
 
 impl Hand {
-    pub fn parse(c: &char) -> Hand {
+    pub fn parse(c: &char) -> Option<Hand> {
         match c {
-            'A' | 'X' => Hand::Rock,
-            'B' | 'Y' => Hand::Paper,
-            'C' | 'Z' => Hand::Scissors,
-            _ => unreachable!(),
+            'A' | 'X' => Some(Hand::Rock),
+            'B' | 'Y' => Some(Hand::Paper),
+            'C' | 'Z' => Some(Hand::Scissors),
+            _ => None,
         }
     }
 
@@ -103,17 +102,29 @@ impl From<Hand> for NeedsTo {
 
 #[cfg(test)]
 mod tests {
-    const INPUT: &str = "src/input/02.txt";
+    use crate::inputs::{example, real};
 
     #[test]
     fn one() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(17189, super::one(&input));
+        let input = real(2);
+        assert_eq!(Ok(17189), super::one(&input));
     }
 
     #[test]
     fn two() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(13490, super::two(&input));
+        let input = real(2);
+        assert_eq!(Ok(13490), super::two(&input));
+    }
+
+    #[test]
+    fn one_example() {
+        let input = example(2, 1);
+        assert_eq!(Ok(15), super::one(&input));
+    }
+
+    #[test]
+    fn two_example() {
+        let input = example(2, 1);
+        assert_eq!(Ok(12), super::two(&input));
     }
 }
@@ -1,33 +1,49 @@
 use itertools::Itertools;
 use std::collections::HashSet;
 
+use crate::parse::{self, ParseError};
+
 #[allow(dead_code)]
-pub fn one(input: &str) -> u32 {
-    input
-        .lines()
+pub fn one(input: &str) -> Result<u32, ParseError> {
+    Ok(rucksacks(input)?
+        .into_iter()
         .map(|l| {
             let (a, b) = l.split_at(l.len() / 2);
             let ac = a.chars().collect::<HashSet<_>>();
             let bc = b.chars().collect::<HashSet<_>>();
-            ac.intersection(&bc).map(|x| priority(x)).sum::<u32>()
+            ac.intersection(&bc).map(priority).sum::<u32>()
         })
-        .sum()
+        .sum())
 }
 
 #[allow(dead_code)]
-pub fn two(input: &str) -> u32 {
-    input
-        .lines()
+pub fn two(input: &str) -> Result<u32, ParseError> {
+    Ok(rucksacks(input)?
+        .into_iter()
         .chunks(3)
         .into_iter()
         .map(|group| {
             let rucksacks: Vec<HashSet<_>> = group.map(|l| l.chars().collect()).collect();
             let mut shared = rucksacks[0].clone();
-            shared.retain(|x| rucksacks[1].contains(&x));
-            shared.retain(|x| rucksacks[2].contains(&x));
-            shared.iter().map(|x| priority(x)).sum::<u32>()
+            shared.retain(|x| rucksacks[1].contains(x));
+            shared.retain(|x| rucksacks[2].contains(x));
+            shared.iter().map(priority).sum::<u32>()
         })
-        .sum()
+        .sum())
+}
+
+fn rucksacks(input: &str) -> Result<Vec<&str>, ParseError> {
+    let rucksacks = parse::lines(3, input, "an even number of letters", |l| {
+        (l.len() % 2 == 0 && l.chars().all(|c| c.is_ascii_alphabetic())).then_some(l)
+    })?;
+    if rucksacks.len() % 3 != 0 {
+        return Err(ParseError::new(
+            3,
+            rucksacks.len() + 1,
+            "groups of three rucksacks",
+        ));
+    }
+    Ok(rucksacks)
 }
 
 fn priority(c: &char) -> u32 {
@@ -40,17 +56,29 @@ fn priority(c: &char) -> u32 {
 
 #[cfg(test)]
 mod tests {
-    const INPUT: &str = "src/input/03.txt";
+    use crate::inputs::{example, real};
 
     #[test]
     pub fn one() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(7597, super::one(&input));
+        let input = real(3);
+        assert_eq!(Ok(7597), super::one(&input));
     }
 
     #[test]
     pub fn two() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(2607, super::two(&input));
+        let input = real(3);
+        assert_eq!(Ok(2607), super::two(&input));
+    }
+
+    #[test]
+    fn one_example() {
+        let input = example(3, 1);
+        assert_eq!(Ok(157), super::one(&input));
+    }
+
+    #[test]
+    fn two_example() {
+        let input = example(3, 1);
+        assert_eq!(Ok(70), super::two(&input));
     }
 }
@@ -1,5 +1,7 @@
 use std::str::FromStr;
 
+use crate::parse::{self, ParseError};
+
 #[derive(Clone, Copy, Debug)]
 struct Range {
     from: u32,
@@ -24,60 +26,76 @@ impl Range {
             return Some(Overlap::Full);
         }
 
-        return Some(Overlap::Partial);
+        Some(Overlap::Partial)
     }
 }
 
 impl FromStr for Range {
-    type Err = String;
+    type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(s.split_once('-')
-            .map(|(from, to)| (from.parse().unwrap(), to.parse().unwrap()))
-            .map(|(from, to)| Range { from, to })
-            .unwrap())
+        let (from, to) = s.split_once('-').ok_or(())?;
+        Ok(Range {
+            from: from.parse().map_err(|_| ())?,
+            to: to.parse().map_err(|_| ())?,
+        })
     }
 }
 
-pub fn count_pairs(input: &str, full: bool) -> u32 {
-    input
-        .lines()
-        .map(|l| l.split_once(',').unwrap())
-        .map(|(a, b)| (Range::from_str(a).unwrap(), Range::from_str(b).unwrap()))
+pub fn count_pairs(input: &str, full: bool) -> Result<u32, ParseError> {
+    let pairs = parse::lines(4, input, "two ranges like `2-4,6-8`", |l| {
+        let (a, b) = l.split_once(',')?;
+        Some((Range::from_str(a).ok()?, Range::from_str(b).ok()?))
+    })?;
+
+    Ok(pairs
+        .into_iter()
         .filter(|(a, b)| {
             let m = a.overlaps(b);
             if full {
-                matches!(m, Some(_))
+                m.is_some()
             } else {
                 matches!(m, Some(Overlap::Full))
             }
         })
-        .count() as u32
+        .count() as u32)
 }
 
 #[allow(dead_code)]
-pub fn one(input: &str) -> u32 {
+pub fn one(input: &str) -> Result<u32, ParseError> {
     count_pairs(input, false)
 }
 
 #[allow(dead_code)]
-pub fn two(input: &str) -> u32 {
+pub fn two(input: &str) -> Result<u32, ParseError> {
     count_pairs(input, true)
 }
 
 #[cfg(test)]
 mod tests {
-    const INPUT: &str = "src/input/04.txt";
+    use crate::inputs::{example, real};
 
     #[test]
     fn one() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(518, super::one(&input));
+        let input = real(4);
+        assert_eq!(Ok(518), super::one(&input));
     }
 
     #[test]
     fn two() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(909, super::two(&input));
+        let input = real(4);
+        assert_eq!(Ok(909), super::two(&input));
+    }
+
+    #[test]
+    fn one_example() {
+        let input = example(4, 1);
+        assert_eq!(Ok(2), super::one(&input));
+    }
+
+    #[test]
+    fn two_example() {
+        let input = example(4, 1);
+        assert_eq!(Ok(4), super::two(&input));
     }
 }
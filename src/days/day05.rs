@@ -3,10 +3,11 @@ use std::collections::{BTreeMap, VecDeque};
 
 use itertools::Itertools;
 
+use crate::parse::{self, ParseError};
+
 #[allow(dead_code)]
-pub fn one(input: &str) -> String {
-    let (mut map, moves) = parse_input(input);
-    dbg!(&map);
+pub fn one(input: &str) -> Result<String, ParseError> {
+    let (mut map, moves) = parse_input(input)?;
     for (n, from, to) in moves {
         for _ in 0..n {
             let from_char = map.get_mut(&from).unwrap().pop_back().unwrap();
@@ -14,12 +15,12 @@ pub fn one(input: &str) -> String {
         }
     }
 
-    map.values().map(|v| v.back().unwrap()).collect()
+    Ok(map.values().map(|v| v.back().unwrap()).collect())
 }
 
 #[allow(dead_code)]
-pub fn two(input: &str) -> String {
-    let (mut map, moves) = parse_input(input);
+pub fn two(input: &str) -> Result<String, ParseError> {
+    let (mut map, moves) = parse_input(input)?;
     for (n, from, to) in moves {
         let from_vec = map.get_mut(&from).unwrap();
         let crates = from_vec.drain((from_vec.len() - n)..).collect_vec();
@@ -28,18 +29,26 @@ pub fn two(input: &str) -> String {
         }
     }
 
-    map.values().map(|v| v.back().unwrap()).collect()
+    Ok(map.values().map(|v| v.back().unwrap()).collect())
 }
 
-fn parse_input(input: &str) -> (BTreeMap<usize, VecDeque<char>>, Vec<(usize, usize, usize)>) {
+type Stacks = BTreeMap<usize, VecDeque<char>>;
+type Move = (usize, usize, usize);
+
+pub(crate) fn parse_input(input: &str) -> Result<(Stacks, Vec<Move>), ParseError> {
+    let lines = input.lines().collect_vec();
+    let split = lines.iter().position(|l| l.is_empty()).ok_or_else(|| {
+        ParseError::new(5, lines.len() + 1, "an empty line after the crate stacks")
+    })?;
+
     // crate stack
     let mut map: BTreeMap<usize, VecDeque<char>> = BTreeMap::new();
-    for l in input.lines().take_while(|l| !l.is_empty()) {
+    for l in &lines[..split] {
         let chars = l
             .chars()
             .collect_vec()
             .chunks(4)
-            .map(|c| if c[1] == ' ' { None } else { Some(c[1]) })
+            .map(|c| c.get(1).copied().filter(|c| c.is_ascii_uppercase()))
             .collect_vec();
         for (i, c) in chars.into_iter().enumerate() {
             if let Some(c) = c {
@@ -49,28 +58,46 @@ fn parse_input(input: &str) -> (BTreeMap<usize, VecDeque<char>>, Vec<(usize, usi
     }
 
     // moves
-    let moves = input
-        .lines()
-        .skip_while(|l| !l.is_empty())
-        .filter_map(|l| scan_fmt!(l, "move {} from {} to {}", usize, usize, usize).ok())
-        .collect_vec();
+    let moves = parse::lines_from(
+        5,
+        &lines[split + 1..].join("\n"),
+        split + 2,
+        "`move N from A to B` between two stacks",
+        |l| {
+            scan_fmt!(l, "move {} from {} to {}", usize, usize, usize)
+                .ok()
+                .filter(|(_, from, to)| map.contains_key(from) && map.contains_key(to))
+        },
+    )?;
 
-    (map, moves)
+    Ok((map, moves))
 }
 
 #[cfg(test)]
 mod tests {
-    const INPUT: &str = "src/input/05.txt";
+    use crate::inputs::{example, real};
 
     #[test]
     fn one() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!("BSDMQFLSP", super::one(&input));
+        let input = real(5);
+        assert_eq!(Ok("BSDMQFLSP"), super::one(&input).as_deref());
     }
 
     #[test]
     fn two() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!("PGSQBFLDP", super::two(&input));
+        let input = real(5);
+        assert_eq!(Ok("PGSQBFLDP"), super::two(&input).as_deref());
+    }
+
+    #[test]
+    fn one_example() {
+        let input = example(5, 1);
+        assert_eq!(Ok("CMZ"), super::one(&input).as_deref());
+    }
+
+    #[test]
+    fn two_example() {
+        let input = example(5, 1);
+        assert_eq!(Ok("MCD"), super::two(&input).as_deref());
     }
 }
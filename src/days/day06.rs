@@ -1,15 +1,26 @@
 use itertools::Itertools;
 
+use crate::parse::ParseError;
+
 #[allow(dead_code)]
-pub fn one(input: &str) -> u32 {
-    let signal: Vec<char> = input.lines().map(|l| l.chars().collect()).next().unwrap();
-    marker_idx(&signal, 4).unwrap() as u32
+pub fn one(input: &str) -> Result<u32, ParseError> {
+    let signal = signal(input)?;
+    Ok(marker_idx(&signal, 4).unwrap() as u32)
 }
 
 #[allow(dead_code)]
-pub fn two(input: &str) -> u32 {
-    let signal: Vec<char> = input.lines().map(|l| l.chars().collect()).next().unwrap();
-    marker_idx(&signal, 14).unwrap() as u32
+pub fn two(input: &str) -> Result<u32, ParseError> {
+    let signal = signal(input)?;
+    Ok(marker_idx(&signal, 14).unwrap() as u32)
+}
+
+fn signal(input: &str) -> Result<Vec<char>, ParseError> {
+    input
+        .lines()
+        .next()
+        .filter(|l| !l.is_empty())
+        .map(|l| l.chars().collect())
+        .ok_or_else(|| ParseError::new(6, 1, "a datastream"))
 }
 
 fn marker_idx(signal: &[char], marker_length: usize) -> Option<usize> {
@@ -27,17 +38,29 @@ fn marker_idx(signal: &[char], marker_length: usize) -> Option<usize> {
 
 #[cfg(test)]
 mod tests {
-    const INPUT: &str = "src/input/06.txt";
+    use crate::inputs::{example, real};
 
     #[test]
     fn one() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(1300, super::one(&input));
+        let input = real(6);
+        assert_eq!(Ok(1300), super::one(&input));
     }
 
     #[test]
     fn two() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(3986, super::two(&input));
+        let input = real(6);
+        assert_eq!(Ok(3986), super::two(&input));
+    }
+
+    #[test]
+    fn one_example() {
+        let input = example(6, 1);
+        assert_eq!(Ok(5), super::one(&input));
+    }
+
+    #[test]
+    fn two_example() {
+        let input = example(6, 1);
+        assert_eq!(Ok(23), super::two(&input));
     }
 }
@@ -1,35 +1,36 @@
 use itertools::Itertools;
 use std::collections::HashMap;
 
+use crate::parse::ParseError;
+
 #[allow(dead_code)]
-pub fn one(input: &str) -> u64 {
-    let dirs = dir_sizes(parse_files(input));
+pub fn one(input: &str) -> Result<u64, ParseError> {
+    let dirs = dir_sizes(parse_files(input)?);
 
     let sum = dirs
-        .iter()
-        .filter_map(|(_, v)| if *v <= 100_000 { Some(*v) } else { None })
+        .values()
+        .filter_map(|v| if *v <= 100_000 { Some(*v) } else { None })
         .sum();
 
-    sum
+    Ok(sum)
 }
 
 #[allow(dead_code)]
-pub fn two(input: &str) -> u64 {
-    let dirs = dir_sizes(parse_files(input));
+pub fn two(input: &str) -> Result<u64, ParseError> {
+    let dirs = dir_sizes(parse_files(input)?);
 
-    let space = 70_000_000 as u64;
-    let min_needed_free = 30_000_000 as u64;
+    let space = 70_000_000_u64;
+    let min_needed_free = 30_000_000_u64;
     let used = *dirs.get(&vec![]).unwrap();
     let needed_to_delete = min_needed_free - (space - used);
 
     let result = dirs
         .values()
         .sorted()
-        .skip_while(|v| **v < needed_to_delete)
-        .next()
+        .find(|v| **v >= needed_to_delete)
         .unwrap();
 
-    *result
+    Ok(*result)
 }
 
 fn dir_sizes(files: HashMap<Vec<String>, u64>) -> HashMap<Vec<String>, u64> {
@@ -37,27 +38,26 @@ fn dir_sizes(files: HashMap<Vec<String>, u64>) -> HashMap<Vec<String>, u64> {
 
     for (path, size) in files {
         let mut p = path.clone();
-        while let Some(_) = p.pop() {
-            (*map.entry(p.clone()).or_default()) += size as u64;
+        while p.pop().is_some() {
+            (*map.entry(p.clone()).or_default()) += size;
         }
     }
 
     map
 }
 
-fn parse_files(input: &str) -> HashMap<Vec<String>, u64> {
+pub(crate) fn parse_files(input: &str) -> Result<HashMap<Vec<String>, u64>, ParseError> {
     let mut map = HashMap::new();
 
     let mut ls = false;
     let mut path = Vec::new();
 
-    for l in input.lines() {
-        if l.starts_with('$') {
+    for (i, l) in input.lines().enumerate() {
+        let err = || ParseError::new(7, i + 1, "`$ cd DIR`, `$ ls` or the output of `ls`");
+        if let Some(l) = l.strip_prefix("$ ") {
             ls = false;
 
-            let l = l.trim_start_matches("$ ");
-            if l.starts_with("cd") {
-                let dir = l.split_once(' ').unwrap().1;
+            if let Some(dir) = l.strip_prefix("cd ") {
                 match dir {
                     "/" => {
                         path.clear();
@@ -69,37 +69,53 @@ fn parse_files(input: &str) -> HashMap<Vec<String>, u64> {
                         path.push(d.to_owned());
                     }
                 }
-            } else if l.starts_with("ls") {
+            } else if l == "ls" {
                 ls = true;
+            } else {
+                return Err(err());
             }
         } else if ls {
-            if let Some((Some(size), filename)) = l
-                .split_once(' ')
-                .map(|(size, filename)| (size.parse::<u64>().ok(), filename))
-            {
-                let mut path = path.clone();
-                path.push(filename.to_owned());
-                map.insert(path, size);
+            let (size, filename) = l.split_once(' ').ok_or_else(err)?;
+            if size == "dir" {
+                continue;
             }
+            let size = size.parse::<u64>().map_err(|_| err())?;
+            let mut path = path.clone();
+            path.push(filename.to_owned());
+            map.insert(path, size);
+        } else {
+            return Err(err());
         }
     }
 
-    map
+    Ok(map)
 }
 
 #[cfg(test)]
 mod tests {
-    const INPUT: &str = "src/input/07.txt";
+    use crate::inputs::{example, real};
 
     #[test]
     fn one() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(2104783, super::one(&input));
+        let input = real(7);
+        assert_eq!(Ok(2104783), super::one(&input));
     }
 
     #[test]
     fn two() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(5883165, super::two(&input));
+        let input = real(7);
+        assert_eq!(Ok(5883165), super::two(&input));
+    }
+
+    #[test]
+    fn one_example() {
+        let input = example(7, 1);
+        assert_eq!(Ok(95437), super::one(&input));
+    }
+
+    #[test]
+    fn two_example() {
+        let input = example(7, 1);
+        assert_eq!(Ok(24933642), super::two(&input));
     }
 }
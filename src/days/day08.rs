@@ -1,27 +1,26 @@
-use crate::utils::grid::{Direction, Grid, Point2};
+use crate::{
+    parse::ParseError,
+    utils::grid::{DenseGrid, Direction, Grid, Point2},
+};
 
 #[allow(dead_code)]
-pub fn one(input: &str) -> u32 {
-    let grid = parse_grid(input);
-    grid.map
-        .keys()
-        .map(|p| is_visible(*p, &grid))
+pub fn one(input: &str) -> Result<u32, ParseError> {
+    let grid = parse_grid(input)?;
+    Ok(grid
+        .points()
+        .map(|p| is_visible(p, &grid))
         .filter(|b| *b)
-        .count() as u32
+        .count() as u32)
 }
 
 #[allow(dead_code)]
-pub fn two(input: &str) -> u32 {
-    let grid = parse_grid(input);
-    grid.map
-        .keys()
-        .map(|p| scenic_score(*p, &grid))
-        .max()
-        .unwrap()
+pub fn two(input: &str) -> Result<u32, ParseError> {
+    let grid = parse_grid(input)?;
+    Ok(grid.points().map(|p| scenic_score(p, &grid)).max().unwrap())
 }
 
-fn scenic_score(p: Point2, grid: &Grid<u32>) -> u32 {
-    let height = grid.map.get(&p).unwrap();
+fn scenic_score(p: Point2, grid: &DenseGrid<u32>) -> u32 {
+    let height = &grid[p];
     Direction::cardinals()
         .map(|d| {
             let mut cnt = 0;
@@ -34,39 +33,52 @@ fn scenic_score(p: Point2, grid: &Grid<u32>) -> u32 {
             cnt
         })
         .into_iter()
-        .fold(1, |acc, x| acc * x)
+        .product()
 }
 
-fn is_visible(p: Point2, grid: &Grid<u32>) -> bool {
-    let height = grid.map.get(&p).unwrap();
+fn is_visible(p: Point2, grid: &DenseGrid<u32>) -> bool {
+    let height = &grid[p];
     Direction::cardinals()
         .map(|d| grid.line_starting_from(p, d).skip(1).all(|n| n < height))
         .into_iter()
         .any(|b| b)
 }
 
-fn parse_grid(input: &str) -> Grid<u32> {
-    Grid::from_vec(
-        input
-            .lines()
-            .map(|l| l.chars().map(|c| c.to_digit(10)).collect())
-            .collect(),
-    )
+pub(crate) fn parse_grid(input: &str) -> Result<DenseGrid<u32>, ParseError> {
+    let grid = Grid::parse(input, |c| c.to_digit(10).map(Some).ok_or("a digit"))
+        .map_err(|e| ParseError::from_grid(8, e))?;
+    let width = grid.width;
+    DenseGrid::from(grid).filled().ok_or_else(|| {
+        let line = input.lines().position(|l| l.len() != width).unwrap();
+        ParseError::new(8, line + 1, format!("a row of {} trees", width))
+    })
 }
 
 #[cfg(test)]
 mod tests {
-    const INPUT: &str = "src/input/08.txt";
+    use crate::inputs::{example, real};
 
     #[test]
     fn one() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(1717, super::one(&input));
+        let input = real(8);
+        assert_eq!(Ok(1717), super::one(&input));
     }
 
     #[test]
     fn two() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(321975, super::two(&input));
+        let input = real(8);
+        assert_eq!(Ok(321975), super::two(&input));
+    }
+
+    #[test]
+    fn one_example() {
+        let input = example(8, 1);
+        assert_eq!(Ok(21), super::one(&input));
+    }
+
+    #[test]
+    fn two_example() {
+        let input = example(8, 1);
+        assert_eq!(Ok(8), super::two(&input));
     }
 }
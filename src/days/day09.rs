@@ -1,79 +1,116 @@
-use scan_fmt::scan_fmt;
+use std::collections::HashSet;
 
-use crate::utils::grid::{Direction, Grid, Point2};
+use crate::{
+    days::SolveError,
+    parse::{self, ParseError},
+    utils::{
+        grid::{BoundingBox2, Direction, Point2},
+        render::{Recorder, Rgb},
+    },
+};
 
 #[allow(dead_code)]
-pub fn one(input: &str) -> u32 {
-    let mut board = Board::new(2);
-    for (dir, n) in parse_moves(input) {
-        for _ in 0..n {
-            board.update(dir);
-        }
-    }
-    board.count_tail_visited()
+pub fn one(input: &str) -> Result<u32, ParseError> {
+    simulate(input, 2, &mut Recorder::off())
 }
 
 #[allow(dead_code)]
-pub fn two(input: &str) -> u32 {
-    let mut board = Board::new(10);
-    for (dir, n) in parse_moves(input) {
+pub fn two(input: &str) -> Result<u32, ParseError> {
+    simulate(input, 10, &mut Recorder::off())
+}
+
+/// Records the rope after every step of `part`.
+pub(crate) fn record(input: &str, part: usize, recorder: &mut Recorder) -> Result<(), SolveError> {
+    simulate(input, if part == 1 { 2 } else { 10 }, recorder)
+        .map(|_| ())
+        .map_err(Into::into)
+}
+
+fn simulate(input: &str, knots: usize, recorder: &mut Recorder) -> Result<u32, ParseError> {
+    let mut rope = Rope::new(knots);
+    for (dir, n) in parse_moves(input)? {
         for _ in 0..n {
-            board.update(dir);
+            rope.step(dir);
+            recorder.record_with(|| rope.snapshot());
         }
     }
-    board.count_tail_visited()
+    Ok(rope.visited(knots - 1).len() as u32)
+}
+
+/// Parses moves like `R 4`, or `UL 2` for a diagonal move.
+pub(crate) fn parse_moves(input: &str) -> Result<Vec<(Direction, usize)>, ParseError> {
+    parse::lines(9, input, "a move like `R 4` or `UL 2`", |l| {
+        let (dir, n) = l.split_once(' ')?;
+        let dir = match dir {
+            "L" => Direction::West,
+            "R" => Direction::East,
+            "D" => Direction::South,
+            "U" => Direction::North,
+            "UL" => Direction::NorthWest,
+            "UR" => Direction::NorthEast,
+            "DL" => Direction::SouthWest,
+            "DR" => Direction::SouthEast,
+            _ => return None,
+        };
+        Some((dir, n.parse().ok()?))
+    })
 }
 
-fn parse_moves(input: &str) -> Vec<(Direction, usize)> {
-    input
-        .lines()
-        .map(|l| scan_fmt!(l, "{} {}", char, usize).unwrap())
-        .map(|(c, n)| {
-            (
-                match c {
-                    'L' => Direction::West,
-                    'R' => Direction::East,
-                    'D' => Direction::South,
-                    'U' => Direction::North,
-                    _ => panic!("wrong char"),
-                },
-                n,
-            )
-        })
-        .collect()
+/// A rope of knots, each one following the one before it. Knot 0 is the head.
+#[derive(Clone, Debug)]
+pub(crate) struct Rope {
+    knots: Vec<Point2>,
+    /// The points each knot has been on.
+    visited: Vec<HashSet<Point2>>,
+    /// The position of every knot after every step, starting with where they started.
+    trace: Option<Vec<Vec<Point2>>>,
 }
 
-struct Board {
-    grid: Grid<bool>,
-    head: Point2,
-    tail: Vec<Point2>,
+/// A summary of where the knots of a [`Rope`] have been.
+#[allow(dead_code)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct RopeStats {
+    /// The number of distinct points each knot has been on.
+    pub visited: Vec<usize>,
+    /// The smallest box around every point any knot has been on.
+    pub extent: BoundingBox2,
 }
 
-impl Board {
-    pub fn new(length: usize) -> Self {
-        let mut grid = Grid::new();
-        grid.map.insert(Point2::zero(), true);
-        Board {
-            grid,
-            head: Point2::zero(),
-            tail: (0..length - 1).map(|_| Point2::zero()).collect(),
+#[allow(dead_code)]
+impl Rope {
+    /// A rope of `knots` knots, all starting at the origin. Panics if there are no knots.
+    pub fn new(knots: usize) -> Self {
+        assert!(knots > 0, "a rope needs at least one knot");
+        Rope {
+            knots: vec![Point2::zero(); knots],
+            visited: vec![HashSet::from([Point2::zero()]); knots],
+            trace: None,
         }
     }
 
-    pub fn update(&mut self, dir: Direction) {
-        // update head
-        self.head = self.head + dir.offset();
+    /// Keeps the positions of every knot after every step, for [`Rope::trace`].
+    pub fn traced(mut self) -> Self {
+        self.trace = Some(vec![self.knots.clone()]);
+        self
+    }
 
-        // update tail
-        let mut head = self.head;
-        for tail in self.tail.iter_mut() {
+    /// Moves the head one step in `dir`, which may be diagonal, and lets the other knots follow.
+    pub fn step(&mut self, dir: Direction) {
+        self.knots[0] = self.knots[0] + dir.offset();
+
+        for i in 1..self.knots.len() {
+            let (head, tail) = (self.knots[i - 1], self.knots[i]);
             if tail.x.abs_diff(head.x) > 1 || tail.y.abs_diff(head.y) > 1 {
-                *tail = head + shorten_point(*tail - head);
+                self.knots[i] = head + shorten_point(tail - head);
             }
-            head = *tail;
         }
 
-        *(self.grid.map.entry(head).or_default()) = true;
+        for (visited, knot) in self.visited.iter_mut().zip(&self.knots) {
+            visited.insert(*knot);
+        }
+        if let Some(trace) = &mut self.trace {
+            trace.push(self.knots.clone());
+        }
 
         fn shorten_point(p: Point2) -> Point2 {
             // Shortens `p` (which we treat as a vector here) by one unit on each axis
@@ -84,24 +121,112 @@ impl Board {
         }
     }
 
-    pub fn count_tail_visited(&self) -> u32 {
-        self.grid.map.values().filter(|v| **v).count() as u32
+    pub fn knots(&self) -> &[Point2] {
+        &self.knots
+    }
+
+    pub fn visited(&self, knot: usize) -> &HashSet<Point2> {
+        &self.visited[knot]
+    }
+
+    /// The positions of every knot after every step, if the rope is [traced](Rope::traced).
+    pub fn trace(&self) -> Option<&[Vec<Point2>]> {
+        self.trace.as_deref()
+    }
+
+    pub fn stats(&self) -> RopeStats {
+        RopeStats {
+            visited: self.visited.iter().map(HashSet::len).collect(),
+            extent: BoundingBox2::from_points(self.visited.iter().flatten().copied()),
+        }
+    }
+
+    /// The points the tail visited in green, the knots in white and the head in red.
+    fn snapshot(&self) -> impl Iterator<Item = (Point2, Rgb)> + '_ {
+        let tail = &self.visited[self.knots.len() - 1];
+        let visited = tail.iter().map(|p| (*p, Rgb(40, 120, 40)));
+        let knots = self.knots[1..].iter().rev().map(|p| (*p, Rgb::WHITE));
+        visited
+            .chain(knots)
+            .chain(std::iter::once((self.knots[0], Rgb(255, 0, 0))))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    const INPUT: &str = "src/input/09.txt";
+    use super::Rope;
+    use crate::{
+        inputs::{example, real},
+        utils::grid::{Direction, Point2},
+    };
 
     #[test]
     fn one() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(6339, super::one(&input));
+        let input = real(9);
+        assert_eq!(Ok(6339), super::one(&input));
     }
 
     #[test]
     fn two() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(2541, super::two(&input));
+        let input = real(9);
+        assert_eq!(Ok(2541), super::two(&input));
+    }
+
+    #[test]
+    fn one_example() {
+        let input = example(9, 1);
+        assert_eq!(Ok(13), super::one(&input));
+    }
+
+    #[test]
+    fn two_example() {
+        let input = example(9, 1);
+        assert_eq!(Ok(1), super::two(&input));
+    }
+
+    #[test]
+    fn two_larger_example() {
+        let input = example(9, 2);
+        assert_eq!(Ok(36), super::two(&input));
+    }
+
+    #[test]
+    fn stats_and_trace() {
+        let mut rope = Rope::new(3).traced();
+        for (dir, n) in super::parse_moves(&example(9, 1)).unwrap() {
+            for _ in 0..n {
+                rope.step(dir);
+            }
+        }
+        let stats = rope.stats();
+        assert_eq!(13, stats.visited[1]);
+        assert_eq!(stats.visited[2], rope.visited(2).len());
+        assert!(stats.visited[0] >= stats.visited[1] && stats.visited[1] >= stats.visited[2]);
+        assert_eq!(
+            Point2 { x: 5, y: 4 },
+            stats.extent.highest - stats.extent.lowest
+        );
+
+        // 24 steps, and where the rope started
+        let trace = rope.trace().unwrap();
+        assert_eq!(25, trace.len());
+        assert_eq!(vec![Point2::zero(); 3], trace[0]);
+        assert_eq!(rope.knots(), &trace[24][..]);
+    }
+
+    #[test]
+    fn diagonal_moves() {
+        let moves = super::parse_moves("UR 3\nDL 1").unwrap();
+        assert_eq!(Direction::NorthEast, moves[0].0);
+
+        let mut rope = Rope::new(3);
+        for (dir, n) in moves {
+            for _ in 0..n {
+                rope.step(dir);
+            }
+        }
+        // the head stepped back onto the middle knot, which doesn't need to follow it
+        let [head, middle, tail] = [(2, -2), (2, -2), (1, -1)].map(|(x, y)| Point2 { x, y });
+        assert_eq!(&[head, middle, tail], rope.knots());
     }
 }
@@ -1,123 +1,204 @@
-use std::str::FromStr;
+use crate::parse::{self, ParseError};
 
 #[allow(dead_code)]
-pub fn one(input: &str) -> u32 {
-    let instr = parse(input);
-    let mut reg: isize = 1;
-    let mut sum = 0;
-    let mut cycle = 1;
-
-    for instr in instr {
-        let count = match instr {
-            Instr::Noop => 1,
-            Instr::Add(_) => 2,
-        };
-        for i in 0..count {
-            if (cycle + 20) % 40 == 0 {
-                let signal = (cycle as isize) * reg;
-                sum += signal;
-            }
+pub fn one(input: &str) -> Result<u32, ParseError> {
+    let program = parse(input)?;
+    let cpu = Cpu::new(&InstructionSet::STANDARD, &program);
+    Ok(signal_strength(cpu) as u32)
+}
 
-            match instr {
-                Instr::Add(v) => {
-                    if i == count - 1 {
-                        reg += v
-                    }
-                }
-                _ => {}
-            }
+#[allow(dead_code)]
+pub fn two(input: &str) -> Result<String, ParseError> {
+    let program = parse(input)?;
+    let cpu = Cpu::new(&InstructionSet::STANDARD, &program);
+    Ok(crt(cpu))
+}
 
-            cycle += 1;
-        }
-    }
+pub(crate) fn parse(input: &str) -> Result<Vec<Instr>, ParseError> {
+    InstructionSet::STANDARD.parse(input)
+}
 
-    sum as u32
+/// The sum of the cycle number times `X` during every 40th cycle, starting with the 20th.
+fn signal_strength(trace: impl Iterator<Item = (usize, Registers)>) -> isize {
+    trace
+        .filter(|(cycle, _)| (cycle + 20) % 40 == 0)
+        .map(|(cycle, registers)| cycle as isize * registers[X])
+        .sum()
 }
 
-#[allow(dead_code)]
-pub fn two(input: &str) -> String {
-    let instr = parse(input);
-    let mut reg: isize = 1;
-    let mut cycle = 1;
-    let mut buffer = String::new();
-
-    for instr in instr {
-        let count = match instr {
-            Instr::Noop => 1,
-            Instr::Add(_) => 2,
-        };
-        for i in 0..count {
-            let row_pos = (cycle - 1) % 40;
-            if row_pos >= (reg - 1) && row_pos <= (reg + 1) {
-                buffer.push('#');
+/// The pixels drawn by the CRT, one per cycle: lit if the 3 pixels wide sprite centred on `X`
+/// covers the pixel being drawn in its row of 40.
+fn crt(trace: impl Iterator<Item = (usize, Registers)>) -> String {
+    trace
+        .map(|(cycle, registers)| {
+            let column = ((cycle - 1) % 40) as isize;
+            if column.abs_diff(registers[X]) <= 1 {
+                '#'
             } else {
-                buffer.push('.');
+                '.'
             }
+        })
+        .collect()
+}
 
-            match instr {
-                Instr::Add(v) => {
-                    if i == count - 1 {
-                        reg += v
-                    }
-                }
-                _ => {}
-            }
+pub(crate) type Registers = [isize; 4];
 
-            cycle += 1;
-        }
+/// The register the puzzle's instructions work on.
+pub(crate) const X: usize = 0;
+
+/// An instruction the CPU knows about.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Op {
+    pub name: &'static str,
+    /// The number of integer arguments the instruction takes.
+    pub args: usize,
+    /// How many cycles the instruction takes. An instruction can't take less than one, so 0
+    /// counts as 1.
+    pub cycles: usize,
+    /// Updates the registers at the end of the instruction's last cycle.
+    pub effect: fn(&mut Registers, &[isize]),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct InstructionSet<'a> {
+    pub ops: &'a [Op],
+    /// The registers when a program starts.
+    pub registers: Registers,
+}
+
+impl InstructionSet<'static> {
+    /// `noop` and `addx`, with `X` starting at 1.
+    pub const STANDARD: InstructionSet<'static> = InstructionSet {
+        ops: &[
+            Op {
+                name: "noop",
+                args: 0,
+                cycles: 1,
+                effect: |_, _| {},
+            },
+            Op {
+                name: "addx",
+                args: 1,
+                cycles: 2,
+                effect: |registers, args| registers[X] += args[0],
+            },
+        ],
+        registers: [1, 0, 0, 0],
+    };
+}
+
+impl InstructionSet<'_> {
+    /// Parses one instruction per line, like `addx -3`.
+    pub fn parse(&self, input: &str) -> Result<Vec<Instr>, ParseError> {
+        let expected = self
+            .ops
+            .iter()
+            .map(|op| format!("`{}{}`", op.name, " N".repeat(op.args)))
+            .collect::<Vec<_>>()
+            .join(" or ");
+        parse::lines(10, input, &expected, |l| {
+            let mut words = l.split(' ');
+            let name = words.next()?;
+            let op = self.ops.iter().position(|op| op.name == name)?;
+            let args = words.map(|w| w.parse().ok()).collect::<Option<Vec<_>>>()?;
+            (args.len() == self.ops[op].args).then_some(Instr { op, args })
+        })
     }
+}
 
-    buffer
+/// An instruction of a program: the index of its [`Op`] in the instruction set, and its
+/// arguments.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Instr {
+    pub op: usize,
+    pub args: Vec<isize>,
 }
 
-fn parse(input: &str) -> Vec<Instr> {
-    input.lines().map(|l| l.parse().unwrap()).collect()
+/// Runs a program one cycle at a time, yielding the cycle number, starting at 1, and the
+/// registers during that cycle.
+#[allow(dead_code)]
+#[derive(Clone, Debug)]
+pub(crate) struct Cpu<'a> {
+    set: &'a InstructionSet<'a>,
+    program: &'a [Instr],
+    registers: Registers,
+    cycle: usize,
+    /// The instruction being run, and how many of its cycles are done.
+    pc: usize,
+    busy: usize,
+    breakpoints: Vec<usize>,
 }
 
-#[derive(Clone, Copy, Debug)]
-enum Instr {
-    Noop,
-    Add(isize),
+#[allow(dead_code)]
+impl<'a> Cpu<'a> {
+    pub fn new(set: &'a InstructionSet<'a>, program: &'a [Instr]) -> Self {
+        Self {
+            set,
+            program,
+            registers: set.registers,
+            cycle: 1,
+            pc: 0,
+            busy: 0,
+            breakpoints: Vec::new(),
+        }
+    }
+
+    /// Makes [`Cpu::resume`] stop during `cycle`.
+    pub fn break_at(mut self, cycle: usize) -> Self {
+        self.breakpoints.push(cycle);
+        self
+    }
+
+    /// Runs until the next breakpoint, and returns the cycle and registers there. `None` if the
+    /// program ends first.
+    pub fn resume(&mut self) -> Option<(usize, Registers)> {
+        while let Some((cycle, registers)) = self.next() {
+            if self.breakpoints.contains(&cycle) {
+                return Some((cycle, registers));
+            }
+        }
+        None
+    }
 }
 
-impl FromStr for Instr {
-    type Err = String;
+impl Iterator for Cpu<'_> {
+    type Item = (usize, Registers);
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.starts_with("noop") {
-            Ok(Instr::Noop)
-        } else {
-            let v = s.split_once(' ').unwrap().1.parse::<isize>().unwrap();
-            Ok(Instr::Add(v))
+    fn next(&mut self) -> Option<Self::Item> {
+        let instr = self.program.get(self.pc)?;
+        let op = &self.set.ops[instr.op];
+        let during = (self.cycle, self.registers);
+
+        self.cycle += 1;
+        self.busy += 1;
+        if self.busy >= op.cycles {
+            (op.effect)(&mut self.registers, &instr.args);
+            self.pc += 1;
+            self.busy = 0;
         }
+
+        Some(during)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    const INPUT: &str = "src/input/10.txt";
+    use super::{Cpu, InstructionSet, Op};
+    use crate::{
+        inputs::{example, real},
+        ocr,
+    };
 
     #[test]
     fn one() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(13440, super::one(&input));
+        let input = real(10);
+        assert_eq!(Ok(13440), super::one(&input));
     }
 
     #[test]
     fn two() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        let output = super::two(&input);
-        let output = output
-            .chars()
-            .collect::<Vec<_>>()
-            .chunks_exact(40)
-            .map(|c| c.iter().collect::<String>())
-            .collect::<Vec<String>>()
-            .join("\n");
-
-        // Prepend \n so it looks nicer in the test
-        let mut pretty_output = String::from("\n");
-        pretty_output.push_str(&output);
+        let input = real(10);
+        let pretty_output = pretty(&super::two(&input).unwrap());
 
         assert_eq!(
             r"
@@ -129,5 +210,108 @@ mod tests {
 #....###..####..###.#..#.#..#.####.#..#.",
             pretty_output
         );
+        assert_eq!(Ok("PBZGRAZA".to_string()), ocr::read_str(&pretty_output));
+    }
+
+    #[test]
+    fn one_example() {
+        let input = example(10, 1);
+        assert_eq!(Ok(13140), super::one(&input));
+    }
+
+    #[test]
+    fn two_example() {
+        let input = example(10, 1);
+        assert_eq!(
+            r"
+##..##..##..##..##..##..##..##..##..##..
+###...###...###...###...###...###...###.
+####....####....####....####....####....
+#####.....#####.....#####.....#####.....
+######......######......######......####
+#######.......#######.......#######.....",
+            pretty(&super::two(&input).unwrap())
+        );
+    }
+
+    #[test]
+    fn shared_trace() {
+        let program = super::parse(&example(10, 1)).unwrap();
+        let trace = Cpu::new(&InstructionSet::STANDARD, &program).collect::<Vec<_>>();
+        assert_eq!(240, trace.len());
+        assert_eq!(13140, super::signal_strength(trace.iter().copied()));
+        assert_eq!(
+            Ok(super::crt(trace.into_iter())),
+            super::two(&example(10, 1))
+        );
+    }
+
+    #[test]
+    fn breakpoints() {
+        let program = super::parse("noop\naddx 3\naddx -5").unwrap();
+        let mut cpu = Cpu::new(&InstructionSet::STANDARD, &program)
+            .break_at(3)
+            .break_at(5)
+            .break_at(9);
+        assert_eq!(Some((3, [1, 0, 0, 0])), cpu.resume());
+        assert_eq!(Some((5, [4, 0, 0, 0])), cpu.resume());
+        assert_eq!(None, cpu.resume());
+    }
+
+    #[test]
+    fn custom_instructions() {
+        let set = InstructionSet {
+            ops: &[
+                Op {
+                    name: "mov",
+                    args: 2,
+                    cycles: 1,
+                    effect: |registers, args| registers[args[0] as usize] = args[1],
+                },
+                Op {
+                    name: "mul",
+                    args: 2,
+                    cycles: 3,
+                    effect: |registers, args| {
+                        registers[args[0] as usize] *= registers[args[1] as usize]
+                    },
+                },
+            ],
+            registers: [0; 4],
+        };
+        let program = set.parse("mov 1 6\nmov 2 7\nmul 1 2").unwrap();
+        let trace = Cpu::new(&set, &program).collect::<Vec<_>>();
+        assert_eq!(5, trace.len());
+        assert_eq!((5, [0, 6, 7, 0]), trace[4]);
+
+        let err = set.parse("mov 1").unwrap_err();
+        assert_eq!("`mov N N` or `mul N N`", err.expected);
+
+        // an instruction takes at least a cycle
+        let set = InstructionSet {
+            ops: &[Op {
+                cycles: 0,
+                ..set.ops[0]
+            }],
+            ..set
+        };
+        let program = set.parse("mov 1 6\nmov 2 7").unwrap();
+        let trace = Cpu::new(&set, &program).collect::<Vec<_>>();
+        assert_eq!(vec![(1, [0; 4]), (2, [0, 6, 0, 0])], trace);
+    }
+
+    fn pretty(output: &str) -> String {
+        let output = output
+            .chars()
+            .collect::<Vec<_>>()
+            .chunks_exact(40)
+            .map(|c| c.iter().collect::<String>())
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        // Prepend \n so it looks nicer in the test
+        let mut pretty_output = String::from("\n");
+        pretty_output.push_str(&output);
+        pretty_output
     }
 }
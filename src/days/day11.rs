@@ -1,166 +1,1051 @@
-use core::panic;
-use std::{cell::RefCell, collections::VecDeque};
+use std::{collections::HashMap, fmt::Debug, hash::Hash, ops::Range};
 
+use chumsky::prelude::*;
 use itertools::Itertools;
+use num_bigint::BigUint;
+
+use crate::{days::SolveError, parse::ParseError};
 
 #[allow(dead_code)]
-pub fn one(input: &str) -> u64 {
-    let monkeys = parse_monkeys(input);
-    ensue_monkey_business(monkeys, 20, false)
+pub fn one(input: &str) -> Result<u128, SolveError> {
+    let monkeys = parse_monkeys(input)?;
+    ensue_monkey_business(&monkeys, &Rules::PART_ONE).map_err(|e| e.solve_error(&monkeys))
 }
 
 #[allow(dead_code)]
-pub fn two(input: &str) -> u64 {
-    let monkeys = parse_monkeys(input);
-    ensue_monkey_business(monkeys, 10_000, true)
+pub fn two(input: &str) -> Result<u128, SolveError> {
+    let monkeys = parse_monkeys(input)?;
+    ensue_monkey_business(&monkeys, &Rules::PART_TWO).map_err(|e| e.solve_error(&monkeys))
 }
 
-fn ensue_monkey_business(mut monkeys: Vec<Monkey>, rounds: usize, part2: bool) -> u64 {
-    let lcm = monkeys
-        .iter()
-        .map(|m| m.test.0)
-        .fold(1u64, |acc, x| acc * x);
-
-    for _ in 0..rounds {
-        monkeys = round(monkeys, lcm, part2);
-    }
-
-    monkeys
+/// The product of the two highest inspection counts, with worry levels as wide as they need to
+/// be. Two counts that each fit in a `usize` always multiply into a `u128`.
+fn ensue_monkey_business(monkeys: &[Monkey], rules: &Rules) -> Result<u128, WorryError> {
+    Ok(simulate::<u64>(monkeys, rules)
+        .or_else(|e| e.or_wider(|| simulate::<u128>(monkeys, rules)))
+        .or_else(|e| e.or_wider(|| simulate::<BigUint>(monkeys, rules)))?
         .into_iter()
-        .map(|m| m.inspection_count)
         .sorted()
         .rev()
         .take(2)
-        .fold(1u64, |acc, x| acc * x as u64)
+        .fold(1u128, |acc, x| acc * x as u128))
 }
 
-fn round(monkeys: Vec<Monkey>, lcm: u64, part2: bool) -> Vec<Monkey> {
-    // We need runtime borrow-checking
-    let monkeys = monkeys
-        .into_iter()
-        .map(|m| RefCell::new(m.clone()))
-        .collect_vec();
-
-    for m in &monkeys {
-        while let Some(item) = {
-            let mut m = m.borrow_mut();
-            m.items.pop_front()
-        } {
-            m.borrow_mut().inspection_count += 1;
-
-            let (next_m, item) = {
-                let m = m.borrow();
-                let mut item = m.operation.eval(item);
-                if !part2 {
-                    item /= 3;
+/// How the monkeys play.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Rules {
+    pub rounds: usize,
+    /// Whether worry levels are divided by 3 after every inspection.
+    pub relief: bool,
+    /// Whether worry levels are kept modulo the lcm of the monkeys' tests, which doesn't change
+    /// where items are thrown. Without it, worry levels soon overflow anything but a
+    /// [`BigUint`].
+    ///
+    /// That only holds as long as every operation is made of `+`, `*` and `^`, and there's no
+    /// relief: subtracting, dividing or taking a remainder of a reduced worry level can give a
+    /// different remainder than the real one would. Otherwise, worry levels aren't reduced.
+    pub reduce: bool,
+}
+
+impl Rules {
+    pub const PART_ONE: Rules = Rules {
+        rounds: 20,
+        relief: true,
+        reduce: false,
+    };
+
+    pub const PART_TWO: Rules = Rules {
+        rounds: 10_000,
+        relief: false,
+        reduce: true,
+    };
+}
+
+/// Why a worry level couldn't be computed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Fault {
+    /// It doesn't fit in its type, but might in a wider one.
+    Overflow,
+    /// It would be negative, or it got divided by zero.
+    Undefined,
+}
+
+/// A worry level that couldn't be computed when `monkey` inspected an item. Errors order by when
+/// they happened.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct WorryError {
+    /// The round it happened in, starting at 1.
+    pub round: usize,
+    pub monkey: usize,
+    pub fault: Fault,
+}
+
+impl WorryError {
+    /// Tries again with `wider` worry levels if they overflowed, since no type can help
+    /// otherwise.
+    fn or_wider<T>(self, wider: impl FnOnce() -> Result<T, WorryError>) -> Result<T, WorryError> {
+        match self.fault {
+            Fault::Overflow => wider(),
+            Fault::Undefined => Err(self),
+        }
+    }
+
+    /// Blames the operation of the monkey it happened at.
+    fn solve_error(&self, monkeys: &[Monkey]) -> SolveError {
+        let what = match self.fault {
+            Fault::Overflow => "overflows",
+            Fault::Undefined => "goes negative or divides by zero",
+        };
+        let line = monkeys[self.monkey].operation_line;
+        SolveError::unsolvable(
+            11,
+            format!(
+                "the operation on line {} {} in round {}",
+                line, what, self.round
+            ),
+        )
+    }
+}
+
+/// Plays the rounds with worry levels of type `W`. Returns how many items each monkey inspected.
+///
+/// An item's path only depends on its own worry level, so each one is played through all the
+/// rounds on its own, with nothing to allocate along the way.
+pub(crate) fn simulate<W: Worry>(
+    monkeys: &[Monkey],
+    rules: &Rules,
+) -> Result<Vec<usize>, WorryError> {
+    let modulus = modulus(monkeys, rules).map(W::from);
+    let mut counts = vec![0; monkeys.len()];
+    let mut error: Option<WorryError> = None;
+
+    let items = monkeys
+        .iter()
+        .enumerate()
+        .flat_map(|(i, m)| m.items.iter().map(move |&worry| (i, worry)));
+    for (mut holder, worry) in items {
+        let mut item = W::from(worry);
+        for round_number in 1..=rules.rounds {
+            if let Err((monkey, fault)) = play_round(
+                monkeys,
+                &mut holder,
+                &mut item,
+                modulus.as_ref(),
+                rules,
+                |m| counts[m] += 1,
+            ) {
+                // the earliest error is the one a round-by-round simulation would have hit
+                let found = WorryError {
+                    round: round_number,
+                    monkey,
+                    fault,
+                };
+                error = Some(error.map_or(found, |e| e.min(found)));
+                break;
+            }
+        }
+    }
+
+    match error {
+        Some(error) => Err(error),
+        None => Ok(counts),
+    }
+}
+
+/// Plays a round for an item, calling `inspected` with every monkey that inspects it, or returns
+/// the monkey whose worry level couldn't be computed.
+///
+/// The item gets inspected again in the same round if it's thrown to a monkey that's still to
+/// play.
+fn play_round<W: Worry>(
+    monkeys: &[Monkey],
+    holder: &mut usize,
+    item: &mut W,
+    modulus: Option<&W>,
+    rules: &Rules,
+    mut inspected: impl FnMut(usize),
+) -> Result<(), (usize, Fault)> {
+    loop {
+        let inspector = *holder;
+        inspected(inspector);
+        (*holder, *item) = monkeys[inspector]
+            .inspect(item, modulus, rules)
+            .map_err(|fault| (inspector, fault))?;
+        if *holder <= inspector {
+            return Ok(());
+        }
+    }
+}
+
+/// How to play the rounds for a [`Report`].
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Mode {
+    /// Play every round.
+    EveryRound,
+    /// Stop following an item once it's back with the same monkey and worry level it had at the
+    /// start of an earlier round, since it'll go round the same way forever.
+    ///
+    /// Only worry levels that are kept modulo the lcm of the monkeys' tests are sure to repeat,
+    /// so this needs rules that reduce them, see [`Rules::reduce`].
+    SkipCycles,
+}
+
+/// Why a [`Report`] couldn't be made.
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ReportError {
+    Worry(WorryError),
+    /// [`Mode::SkipCycles`] with worry levels that aren't reduced, which might never repeat.
+    Unreduced,
+}
+
+impl From<WorryError> for ReportError {
+    fn from(e: WorryError) -> Self {
+        ReportError::Worry(e)
+    }
+}
+
+#[allow(dead_code)]
+impl ReportError {
+    /// Like [`WorryError::or_wider`].
+    fn or_wider<T>(self, wider: impl FnOnce() -> Result<T, ReportError>) -> Result<T, ReportError> {
+        match self {
+            ReportError::Worry(e) if e.fault == Fault::Overflow => wider(),
+            e => Err(e),
+        }
+    }
+}
+
+/// What happened to every item over the rounds.
+///
+/// Items never affect each other, so each one is followed on its own. Only the order monkeys
+/// inspect items in would tell them apart, and that doesn't change any counts.
+#[allow(dead_code)]
+#[derive(Clone, Debug)]
+pub(crate) struct Report {
+    pub rounds: usize,
+    pub monkeys: usize,
+    /// One for each starting item, in the order the monkeys hold them.
+    pub journeys: Vec<Journey>,
+}
+
+#[allow(dead_code)]
+impl Report {
+    /// Follows the items with worry levels as wide as they need to be.
+    pub fn new(monkeys: &[Monkey], rules: &Rules, mode: Mode) -> Result<Report, ReportError> {
+        Report::with_worry::<u64>(monkeys, rules, mode)
+            .or_else(|e| e.or_wider(|| Report::with_worry::<u128>(monkeys, rules, mode)))
+            .or_else(|e| e.or_wider(|| Report::with_worry::<BigUint>(monkeys, rules, mode)))
+    }
+
+    /// Follows the items with worry levels of type `W`, or returns the first worry level that
+    /// couldn't be computed.
+    pub fn with_worry<W: Worry + Eq + Hash>(
+        monkeys: &[Monkey],
+        rules: &Rules,
+        mode: Mode,
+    ) -> Result<Report, ReportError> {
+        let modulus = modulus(monkeys, rules).map(W::from);
+        if mode == Mode::SkipCycles && modulus.is_none() {
+            return Err(ReportError::Unreduced);
+        }
+        let journeys = monkeys
+            .iter()
+            .enumerate()
+            .flat_map(|(i, m)| m.items.iter().map(move |&worry| (i, worry)))
+            .map(|(monkey, worry)| {
+                Journey::follow(monkeys, monkey, worry, modulus.as_ref(), rules, mode)
+            })
+            .collect_vec();
+
+        // the earliest error is the one a round-by-round simulation would have hit
+        if let Some(error) = journeys.iter().filter_map(|j| j.as_ref().err()).min() {
+            return Err((*error).into());
+        }
+
+        Ok(Report {
+            rounds: rules.rounds,
+            monkeys: monkeys.len(),
+            journeys: journeys.into_iter().map(Result::unwrap).collect(),
+        })
+    }
+
+    /// How many items each monkey inspected during `round`, counted from 0.
+    pub fn inspections_during(&self, round: usize) -> Vec<usize> {
+        assert!(
+            round < self.rounds,
+            "only {} rounds were played",
+            self.rounds
+        );
+        let mut counts = vec![0; self.monkeys];
+        for journey in &self.journeys {
+            for &m in journey.round(round).unwrap_or_default() {
+                counts[m] += 1;
+            }
+        }
+        counts
+    }
+
+    /// How many items each monkey inspected in the first `rounds` rounds.
+    pub fn inspections_after(&self, rounds: usize) -> Vec<usize> {
+        assert!(
+            rounds <= self.rounds,
+            "only {} rounds were played",
+            self.rounds
+        );
+        let mut counts = vec![0; self.monkeys];
+        for journey in &self.journeys {
+            journey.tally(rounds, |visits, _, times| {
+                for &m in visits {
+                    counts[m] += times;
                 }
-                item %= lcm;
-                if item % m.test.0 == 0 {
-                    (m.test.1, item)
-                } else {
-                    (m.test.2, item)
+            });
+        }
+        counts
+    }
+
+    /// How many items each monkey inspected over all the rounds.
+    pub fn inspections(&self) -> Vec<usize> {
+        self.inspections_after(self.rounds)
+    }
+
+    /// How many items each monkey threw to each other monkey over all the rounds, indexed by
+    /// thrower and then catcher.
+    pub fn throws(&self) -> Vec<Vec<usize>> {
+        let mut throws = vec![vec![0; self.monkeys]; self.monkeys];
+        for journey in &self.journeys {
+            journey.tally(self.rounds, |visits, next, times| {
+                for (&from, &to) in visits.iter().chain([&next]).tuple_windows() {
+                    throws[from][to] += times;
                 }
-            };
+            });
+        }
+        throws
+    }
+
+    /// The product of the two highest inspection counts.
+    pub fn monkey_business(&self) -> u128 {
+        self.inspections()
+            .into_iter()
+            .sorted()
+            .rev()
+            .take(2)
+            .fold(1u128, |acc, x| acc * x as u128)
+    }
+}
+
+/// The monkeys that inspected an item.
+#[allow(dead_code)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Journey {
+    /// The monkey holding the item at the start.
+    pub monkey: usize,
+    /// The item's starting worry level.
+    pub worry: u64,
+    /// The monkeys that inspected the item, round by round, until it was either done or started
+    /// going round in a cycle.
+    pub rounds: Vec<Vec<usize>>,
+    /// The rounds that repeat forever after the first of them, if the item ended up in a cycle.
+    pub cycle: Option<Range<usize>>,
+    /// The monkey holding the item after the last round in `rounds`.
+    pub end: usize,
+}
+
+#[allow(dead_code)]
+impl Journey {
+    fn follow<W: Worry + Eq + Hash>(
+        monkeys: &[Monkey],
+        monkey: usize,
+        worry: u64,
+        modulus: Option<&W>,
+        rules: &Rules,
+        mode: Mode,
+    ) -> Result<Journey, WorryError> {
+        let mut seen = HashMap::new();
+        let mut rounds = Vec::new();
+        let mut cycle = None;
+        let (mut holder, mut item) = (monkey, W::from(worry));
+
+        for round in 0..rules.rounds {
+            if mode == Mode::SkipCycles {
+                if let Some(&start) = seen.get(&(holder, item.clone())) {
+                    cycle = Some(start..round);
+                    break;
+                }
+                seen.insert((holder, item.clone()), round);
+            }
+
+            let mut visits = Vec::new();
+            play_round(monkeys, &mut holder, &mut item, modulus, rules, |m| {
+                visits.push(m)
+            })
+            .map_err(|(monkey, fault)| WorryError {
+                round: round + 1,
+                monkey,
+                fault,
+            })?;
+            rounds.push(visits);
+        }
 
-            monkeys[next_m].borrow_mut().items.push_back(item);
+        Ok(Journey {
+            monkey,
+            worry,
+            rounds,
+            cycle,
+            end: holder,
+        })
+    }
+
+    /// The monkeys that inspected the item during `round`, counted from 0, if it was played.
+    pub fn round(&self, round: usize) -> Option<&[usize]> {
+        match &self.cycle {
+            Some(cycle) if round >= cycle.start => {
+                let i = cycle.start + (round - cycle.start) % cycle.len();
+                Some(&self.rounds[i])
+            }
+            _ => self.rounds.get(round).map(Vec::as_slice),
         }
     }
 
-    monkeys.into_iter().map(|m| m.into_inner()).collect()
+    /// The monkeys that inspected the item in the first `rounds` rounds, in order.
+    pub fn visits(&self, rounds: usize) -> impl Iterator<Item = usize> + '_ {
+        (0..rounds).flat_map(|r| self.round(r).unwrap_or_default().iter().copied())
+    }
+
+    /// The monkey holding the item at the start of `round`.
+    fn holder(&self, round: usize) -> usize {
+        self.round(round).map_or(self.end, |visits| visits[0])
+    }
+
+    /// Calls `each` with the monkeys that inspected the item in each of the first `rounds`
+    /// rounds, the monkey it got thrown to at the end of it, and how many times that round
+    /// happened. A cycle is gone round once, however many rounds it covers.
+    fn tally(&self, rounds: usize, mut each: impl FnMut(&[usize], usize, usize)) {
+        let prefix = self.cycle.as_ref().map_or(rounds, |c| c.start.min(rounds));
+        for round in 0..prefix.min(self.rounds.len()) {
+            each(&self.rounds[round], self.holder(round + 1), 1);
+        }
+
+        if let Some(cycle) = self.cycle.clone().filter(|c| rounds > c.start) {
+            let repeated = rounds - cycle.start;
+            let (full, rest) = (repeated / cycle.len(), repeated % cycle.len());
+            for (i, round) in cycle.enumerate() {
+                let times = full + usize::from(i < rest);
+                each(&self.rounds[round], self.holder(round + 1), times);
+            }
+        }
+    }
+}
+
+/// What worry levels are kept modulo, if they can be: the lcm of the monkeys' tests, if it fits
+/// in a `u64` and [`Rules::reduce`] holds.
+fn modulus(monkeys: &[Monkey], rules: &Rules) -> Option<u64> {
+    let reducible = monkeys.iter().all(|m| m.operation.keeps_remainders());
+    if !rules.reduce || rules.relief || !reducible {
+        return None;
+    }
+    monkeys.iter().try_fold(1u64, |lcm, m| {
+        (lcm / gcd(lcm, m.test.0)).checked_mul(m.test.0)
+    })
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// A worry level: an unsigned integer whose arithmetic fails instead of overflowing.
+pub(crate) trait Worry: Clone + Debug + PartialEq + From<u64> {
+    fn checked_add(&self, rhs: &Self) -> Option<Self>;
+    fn checked_sub(&self, rhs: &Self) -> Option<Self>;
+    fn checked_mul(&self, rhs: &Self) -> Option<Self>;
+    fn checked_div(&self, rhs: &Self) -> Option<Self>;
+    fn checked_rem(&self, rhs: &Self) -> Option<Self>;
+    fn checked_pow(&self, exp: u32) -> Option<Self>;
+}
+
+macro_rules! impl_worry {
+    ($($t:ty),*) => {
+        $(
+            impl Worry for $t {
+                fn checked_add(&self, rhs: &Self) -> Option<Self> {
+                    <$t>::checked_add(*self, *rhs)
+                }
+
+                fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+                    <$t>::checked_sub(*self, *rhs)
+                }
+
+                fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+                    <$t>::checked_mul(*self, *rhs)
+                }
+
+                fn checked_div(&self, rhs: &Self) -> Option<Self> {
+                    <$t>::checked_div(*self, *rhs)
+                }
+
+                fn checked_rem(&self, rhs: &Self) -> Option<Self> {
+                    <$t>::checked_rem(*self, *rhs)
+                }
+
+                fn checked_pow(&self, exp: u32) -> Option<Self> {
+                    <$t>::checked_pow(*self, exp)
+                }
+            }
+        )*
+    };
+}
+
+impl_worry!(u64, u128);
+
+impl Worry for BigUint {
+    fn checked_add(&self, rhs: &Self) -> Option<Self> {
+        Some(self + rhs)
+    }
+
+    fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+        (self >= rhs).then(|| self - rhs)
+    }
+
+    fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+        Some(self * rhs)
+    }
+
+    fn checked_div(&self, rhs: &Self) -> Option<Self> {
+        (*rhs != BigUint::from(0u64)).then(|| self / rhs)
+    }
+
+    fn checked_rem(&self, rhs: &Self) -> Option<Self> {
+        (*rhs != BigUint::from(0u64)).then(|| self % rhs)
+    }
+
+    fn checked_pow(&self, exp: u32) -> Option<Self> {
+        Some(self.pow(exp))
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct Monkey {
-    items: VecDeque<u64>,
+    items: Vec<u64>,
     operation: Op,
+    /// The line of the input the operation is on.
+    operation_line: usize,
     test: (u64, usize, usize),
-    inspection_count: usize,
 }
 
-#[derive(Clone, Debug)]
+impl Monkey {
+    /// The monkey an item gets thrown to after this one inspects it, and its new worry level.
+    fn inspect<W: Worry>(
+        &self,
+        item: &W,
+        modulus: Option<&W>,
+        rules: &Rules,
+    ) -> Result<(usize, W), Fault> {
+        // dividing by 3, the modulus or the test's divisor, which are never 0, can't fail
+        let mut item = self.operation.eval(item)?;
+        if rules.relief {
+            item = item.checked_div(&W::from(3)).ok_or(Fault::Undefined)?;
+        }
+        if let Some(modulus) = modulus {
+            item = item.checked_rem(modulus).ok_or(Fault::Undefined)?;
+        }
+        let rem = item
+            .checked_rem(&W::from(self.test.0))
+            .ok_or(Fault::Undefined)?;
+        if rem == W::from(0) {
+            Ok((self.test.1, item))
+        } else {
+            Ok((self.test.2, item))
+        }
+    }
+}
+
+/// The expression a monkey uses to compute a new worry level from the `old` one.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Op {
     Add(Box<Op>, Box<Op>),
+    Sub(Box<Op>, Box<Op>),
     Mul(Box<Op>, Box<Op>),
+    Div(Box<Op>, Box<Op>),
+    Rem(Box<Op>, Box<Op>),
+    Pow(Box<Op>, u32),
     Num(u64),
-    Var(String),
+    Old,
 }
 
 impl Op {
-    pub fn eval(&self, old: u64) -> u64 {
+    /// The new worry level, or why it can't be computed in `W`.
+    ///
+    /// Worry levels are unsigned, so subtraction fails for any `W` once it would go negative,
+    /// as do division and remainder by zero. Only addition, multiplication and powers overflow.
+    pub(crate) fn eval<W: Worry>(&self, old: &W) -> Result<W, Fault> {
+        use Fault::{Overflow, Undefined};
+        match self {
+            Op::Add(l, r) => l.eval(old)?.checked_add(&r.eval(old)?).ok_or(Overflow),
+            Op::Sub(l, r) => l.eval(old)?.checked_sub(&r.eval(old)?).ok_or(Undefined),
+            Op::Mul(l, r) => l.eval(old)?.checked_mul(&r.eval(old)?).ok_or(Overflow),
+            Op::Div(l, r) => l.eval(old)?.checked_div(&r.eval(old)?).ok_or(Undefined),
+            Op::Rem(l, r) => l.eval(old)?.checked_rem(&r.eval(old)?).ok_or(Undefined),
+            Op::Pow(base, exp) => base.eval(old)?.checked_pow(*exp).ok_or(Overflow),
+            Op::Num(n) => Ok(W::from(*n)),
+            Op::Old => Ok(old.clone()),
+        }
+    }
+
+    /// Whether the remainder of the result by any `n` only depends on the remainder of `old` by
+    /// `n`, which is the case with only `+`, `*` and `^`.
+    fn keeps_remainders(&self) -> bool {
         match self {
-            Op::Add(l, r) => l.eval(old) + r.eval(old),
-            Op::Mul(l, r) => l.eval(old) * r.eval(old),
-            Op::Num(i) => *i,
-            Op::Var(name) if name == "old" => old,
-            _ => panic!("unsupported eval"),
+            Op::Add(l, r) | Op::Mul(l, r) => l.keeps_remainders() && r.keeps_remainders(),
+            Op::Pow(base, _) => base.keeps_remainders(),
+            Op::Num(_) | Op::Old => true,
+            Op::Sub(..) | Op::Div(..) | Op::Rem(..) => false,
         }
     }
 }
 
-fn parse_monkeys(input: &str) -> Vec<Monkey> {
-    input
-        .replace("\r\n", "\n")
-        .split("\n\n")
-        .into_iter()
-        .map(|input| {
-            // Parse monkey
-            let mut lines = input.lines();
-
-            // Monkey ID
-            let line = lines.next().unwrap();
-            let _ = scan_fmt::scan_fmt!(line, "Monkey {}:", usize).unwrap();
-
-            // Items
-            let line = lines.next().unwrap();
-            let line = line.strip_prefix("  Starting items: ").unwrap();
-            let items = line.split(", ").map(|x| x.parse().unwrap()).collect();
-
-            // Operation
-            let line = lines.next().unwrap();
-            let parse_atom = |token| match token {
-                "old" => Op::Var("old".to_string()),
-                s => s.parse::<u64>().map(|x| Op::Num(x)).unwrap(),
-            };
-            let line = line.strip_prefix("  Operation: new = ").unwrap();
-            let (l, op, r) = line.split_whitespace().collect_tuple().unwrap();
-            let (l, r) = (parse_atom(l), parse_atom(r));
-            let operation = match op {
-                "*" => Op::Mul(Box::new(l), Box::new(r)),
-                "+" => Op::Add(Box::new(l), Box::new(r)),
-                x => panic!("encountered invalid operation {}", x),
-            };
-
-            // Test
-            let (test, yes, no) = lines.take(3).collect_tuple().unwrap();
-            let strip_num =
-                |line: &str, prefix| line.strip_prefix(prefix).unwrap().parse::<usize>().unwrap();
-            let test = strip_num(test, "  Test: divisible by ");
-            let yes = strip_num(yes, "    If true: throw to monkey ");
-            let no = strip_num(no, "    If false: throw to monkey ");
-
-            Monkey {
-                items,
-                operation,
-                test: (test as u64, yes, no),
-                inspection_count: 0,
-            }
-        })
-        .collect()
+pub(crate) fn parse_monkeys(input: &str) -> Result<Vec<Monkey>, ParseError> {
+    let end = input.lines().count();
+    let mut lines = input.lines().enumerate().peekable();
+    let mut monkeys = Vec::new();
+    let mut targets = Vec::new();
+
+    loop {
+        // monkeys are separated by empty lines
+        while lines.next_if(|(_, l)| l.is_empty()).is_some() {}
+        if lines.peek().is_none() {
+            break;
+        }
+
+        // Monkey ID
+        let (line, id) = field(&mut lines, "Monkey ", end)?;
+        id.strip_suffix(':')
+            .and_then(|id| id.parse::<usize>().ok())
+            .filter(|id| *id == monkeys.len())
+            .ok_or_else(|| ParseError::new(11, line, format!("`Monkey {}:`", monkeys.len())))?;
+
+        // Items
+        let (line, items) = field(&mut lines, "  Starting items: ", end)?;
+        let items = items
+            .split(", ")
+            .map(|x| x.parse().ok())
+            .collect::<Option<_>>()
+            .ok_or_else(|| ParseError::new(11, line, "a list of worry levels"))?;
+
+        // Operation
+        let prefix = "  Operation: new = ";
+        let (operation_line, operation) = field(&mut lines, prefix, end)?;
+        let operation = operation_parser()
+            .then_ignore(chumsky::primitive::end())
+            .parse(operation)
+            .map_err(|errs| {
+                let column = prefix.len() + errs[0].span().start + 1;
+                ParseError::new(
+                    11,
+                    operation_line,
+                    "an operation like `old * 19` or `(old + 3) % 7`",
+                )
+                .at_column(column)
+            })?;
+
+        // Test
+        let number = |(line, n): (usize, &str)| {
+            n.parse::<usize>()
+                .map_err(|_| ParseError::new(11, line, "a number"))
+                .map(|n| (line, n))
+        };
+        let (line, test) = number(field(&mut lines, "  Test: divisible by ", end)?)?;
+        if test == 0 {
+            return Err(ParseError::new(11, line, "a divisor greater than 0"));
+        }
+        let yes = number(field(&mut lines, "    If true: throw to monkey ", end)?)?;
+        let no = number(field(&mut lines, "    If false: throw to monkey ", end)?)?;
+        targets.extend([yes, no]);
+
+        monkeys.push(Monkey {
+            items,
+            operation,
+            operation_line,
+            test: (test as u64, yes.1, no.1),
+        });
+    }
+
+    if let Some((line, _)) = targets.into_iter().find(|(_, m)| *m >= monkeys.len()) {
+        return Err(ParseError::new(11, line, "a monkey that exists"));
+    }
+
+    Ok(monkeys)
+}
+
+/// The next line, which has to start with `prefix`, with its line number and the rest of it.
+fn field<'a>(
+    lines: &mut impl Iterator<Item = (usize, &'a str)>,
+    prefix: &str,
+    end: usize,
+) -> Result<(usize, &'a str), ParseError> {
+    let (i, line) = lines.next().unwrap_or((end, ""));
+    line.strip_prefix(prefix)
+        .map(|rest| (i + 1, rest))
+        .ok_or_else(|| ParseError::new(11, i + 1, format!("`{}`", prefix.trim())))
+}
+
+/// Parses an expression in `old` and unsigned integers, with `+`, `-`, `*`, `/`, `%`, `^` (to a
+/// constant power) and parentheses. `^` binds tightest, then `*`, `/` and `%`, then `+` and `-`.
+fn operation_parser() -> impl Parser<char, Op, Error = Simple<char>> {
+    let number = text::int(10).try_map(|s: String, span| {
+        s.parse::<u64>()
+            .map_err(|e| Simple::custom(span, e.to_string()))
+    });
+    let exponent = text::int(10).try_map(|s: String, span| {
+        s.parse::<u32>()
+            .map_err(|e| Simple::custom(span, e.to_string()))
+    });
+
+    recursive(|expr| {
+        let atom = number
+            .map(Op::Num)
+            .or(text::keyword("old").to(Op::Old))
+            .or(expr.delimited_by(just('('), just(')')))
+            .padded();
+
+        let power = atom
+            .then(just('^').ignore_then(exponent.padded()).repeated())
+            .foldl(|base, exp| Op::Pow(Box::new(base), exp));
+
+        let product = power
+            .clone()
+            .then(one_of("*/%").then(power).repeated())
+            .foldl(|l, (op, r)| {
+                let (l, r) = (Box::new(l), Box::new(r));
+                match op {
+                    '*' => Op::Mul(l, r),
+                    '/' => Op::Div(l, r),
+                    _ => Op::Rem(l, r),
+                }
+            });
+
+        product
+            .clone()
+            .then(one_of("+-").then(product).repeated())
+            .foldl(|l, (op, r)| {
+                let (l, r) = (Box::new(l), Box::new(r));
+                match op {
+                    '+' => Op::Add(l, r),
+                    _ => Op::Sub(l, r),
+                }
+            })
+    })
 }
 
 #[cfg(test)]
 mod tests {
-    const INPUT: &str = "src/input/11.txt";
+    use chumsky::prelude::*;
+    use num_bigint::BigUint;
+
+    use std::{cell::RefCell, collections::VecDeque, hint::black_box, time::Instant};
+
+    use itertools::Itertools;
+
+    use super::{Fault, Mode, Monkey, Report, ReportError, Rules, Worry, WorryError};
+    use crate::inputs::{example, real};
 
     #[test]
     fn one() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(56120, super::one(&input));
+        let input = real(11);
+        assert_eq!(Ok(56120), super::one(&input));
     }
 
     #[test]
     fn two() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(24389045529u64, super::two(&input));
+        let input = real(11);
+        assert_eq!(Ok(24389045529u128), super::two(&input));
+    }
+
+    #[test]
+    fn one_example() {
+        let input = example(11, 1);
+        assert_eq!(Ok(10605), super::one(&input));
+    }
+
+    #[test]
+    fn two_example() {
+        let input = example(11, 1);
+        assert_eq!(Ok(2713310158u128), super::two(&input));
+    }
+
+    #[test]
+    fn operations() {
+        use super::Op::*;
+
+        let parse = |s: &str| super::operation_parser().then_ignore(end()).parse(s).ok();
+        let (old, n) = (|| Box::new(Old), |n| Box::new(Num(n)));
+        assert_eq!(Some(Mul(old(), n(19))), parse("old * 19"));
+        assert_eq!(
+            Some(Add(old(), Box::new(Mul(n(2), Box::new(Pow(old(), 2)))))),
+            parse("old + 2 * old ^ 2")
+        );
+        assert_eq!(
+            Some(Rem(Box::new(Sub(old(), n(3))), n(7))),
+            parse("(old - 3) % 7")
+        );
+        assert_eq!(None, parse("new * 2"));
+        assert_eq!(None, parse("old * "));
+
+        let op = parse("(old + 4) / 2 - 1").unwrap();
+        assert_eq!(Ok(4), op.eval(&6u64));
+        let eval = |op: &str, old: u64| parse(op).unwrap().eval(&old);
+        assert_eq!(Err(Fault::Undefined), eval("old - 7", 6));
+        assert_eq!(Err(Fault::Undefined), eval("old % (old - old)", 6));
+        assert_eq!(Err(Fault::Overflow), eval("old ^ 3", u64::MAX));
+        let big = parse("old ^ 3").unwrap().eval(&BigUint::from(u64::MAX));
+        assert_eq!(Ok(BigUint::from(u64::MAX).pow(3)), big);
+    }
+
+    #[test]
+    fn worry_types() {
+        let monkeys = super::parse_monkeys(&example(11, 1)).unwrap();
+        let rules = Rules {
+            reduce: false,
+            ..Rules::PART_ONE
+        };
+        let small = super::simulate::<u64>(&monkeys, &Rules::PART_ONE).unwrap();
+        let big = super::simulate::<BigUint>(&monkeys, &rules).unwrap();
+        assert_eq!(vec![101, 95, 7, 105], small);
+        assert_eq!(small, big);
+
+        // without the reduction, squared worry levels soon overflow
+        let rules = Rules {
+            rounds: 1000,
+            ..Rules::PART_TWO
+        };
+        assert!(super::simulate::<u64>(&monkeys, &rules).is_ok());
+        let rules = Rules {
+            reduce: false,
+            ..rules
+        };
+        let overflow = super::simulate::<u64>(&monkeys, &rules).unwrap_err();
+        assert_eq!(
+            WorryError {
+                round: 13,
+                monkey: 0,
+                fault: Fault::Overflow,
+            },
+            overflow
+        );
+        let overflow = super::simulate::<u128>(&monkeys, &rules).unwrap_err();
+        assert_eq!(
+            WorryError {
+                round: 31,
+                monkey: 0,
+                fault: Fault::Overflow,
+            },
+            overflow
+        );
+    }
+
+    #[test]
+    fn report() {
+        let monkeys = super::parse_monkeys(&example(11, 1)).unwrap();
+        let cases = [
+            (Rules::PART_ONE, &[Mode::EveryRound][..]),
+            (Rules::PART_TWO, &[Mode::EveryRound, Mode::SkipCycles]),
+        ];
+        for (rules, modes) in cases {
+            let counts = super::simulate::<u64>(&monkeys, &rules).unwrap();
+            for &mode in modes {
+                let report = Report::new(&monkeys, &rules, mode).unwrap();
+                assert_eq!(counts, report.inspections());
+
+                // every inspection ends in a throw
+                let throws = report.throws();
+                let thrown = throws
+                    .iter()
+                    .map(|row| row.iter().sum::<usize>())
+                    .collect_vec();
+                assert_eq!(counts, thrown);
+                assert!((0..4).all(|m| throws[m][m] == 0));
+
+                let during = (0..rules.rounds).map(|r| report.inspections_during(r));
+                let total = during.fold(vec![0; 4], |acc, r| {
+                    acc.iter().zip(r).map(|(a, b)| a + b).collect()
+                });
+                assert_eq!(counts, total);
+            }
+        }
+
+        let report = Report::new(&monkeys, &Rules::PART_TWO, Mode::EveryRound).unwrap();
+        assert_eq!(vec![2, 4, 3, 6], report.inspections_after(1));
+        assert_eq!(vec![99, 97, 8, 103], report.inspections_after(20));
+
+        let report = Report::new(&monkeys, &Rules::PART_ONE, Mode::EveryRound).unwrap();
+        let first = &report.journeys[0];
+        assert_eq!((0, 79), (first.monkey, first.worry));
+        assert_eq!(vec![0, 3, 1, 2, 3], first.visits(2).collect_vec());
+    }
+
+    #[test]
+    fn skip_cycles() {
+        let monkeys = super::parse_monkeys(&example(11, 1)).unwrap();
+        let rules = Rules {
+            rounds: 1_000_000_000,
+            ..Rules::PART_TWO
+        };
+        let report = Report::new(&monkeys, &rules, Mode::SkipCycles).unwrap();
+        assert!(report.journeys.iter().all(|j| j.cycle.is_some()));
+        assert_eq!(
+            vec![52166, 47830, 1938, 52013],
+            report.inspections_after(10_000)
+        );
+
+        // with no relief, every item gets inspected every round
+        let inspections: usize = report.inspections().iter().sum();
+        let visits_per_round = report.inspections_during(rules.rounds - 1);
+        assert!(inspections >= rules.rounds * report.journeys.len());
+        assert!(visits_per_round.iter().sum::<usize>() >= report.journeys.len());
+        // more than fits in a u64
+        assert_eq!(27142382184098982504, report.monkey_business());
+
+        // worry levels that aren't reduced might never repeat
+        for rules in [
+            Rules {
+                reduce: false,
+                ..rules
+            },
+            Rules::PART_ONE,
+        ] {
+            let report = Report::new(&monkeys, &rules, Mode::SkipCycles);
+            assert_eq!(ReportError::Unreduced, report.unwrap_err());
+        }
+    }
+
+    /// The round-by-round simulation `simulate` replaced, which moves every monkey into a
+    /// `RefCell` each round.
+    fn simulate_by_round<W: Worry>(monkeys: &[Monkey], rules: &Rules) -> Vec<usize> {
+        let modulus = super::modulus(monkeys, rules).map(W::from);
+        let mut queues = monkeys
+            .iter()
+            .map(|m| {
+                (
+                    m.items.iter().map(|&i| W::from(i)).collect::<VecDeque<_>>(),
+                    0,
+                )
+            })
+            .collect_vec();
+
+        for _ in 0..rules.rounds {
+            let cells = queues.iter().cloned().map(RefCell::new).collect_vec();
+            for (monkey, cell) in monkeys.iter().zip(&cells) {
+                while let Some(item) = {
+                    let mut queue = cell.borrow_mut();
+                    queue.0.pop_front()
+                } {
+                    cell.borrow_mut().1 += 1;
+                    let (next, item) = monkey.inspect(&item, modulus.as_ref(), rules).unwrap();
+                    cells[next].borrow_mut().0.push_back(item);
+                }
+            }
+            queues = cells.into_iter().map(RefCell::into_inner).collect();
+        }
+
+        queues.into_iter().map(|(_, count)| count).collect()
+    }
+
+    #[test]
+    fn matches_round_by_round() {
+        let monkeys = super::parse_monkeys(&example(11, 1)).unwrap();
+        for rules in [Rules::PART_ONE, Rules::PART_TWO] {
+            assert_eq!(
+                simulate_by_round::<u64>(&monkeys, &rules),
+                super::simulate::<u64>(&monkeys, &rules).unwrap()
+            );
+        }
+    }
+
+    /// Run with `cargo test --release -- --ignored --nocapture bench_simulate`.
+    #[test]
+    #[ignore]
+    fn bench_simulate() {
+        let monkeys = super::parse_monkeys(&example(11, 1)).unwrap();
+        let rules = Rules {
+            rounds: 200_000,
+            ..Rules::PART_TWO
+        };
+        let time = |f: &dyn Fn() -> Vec<usize>| {
+            let start = Instant::now();
+            let counts = black_box(f());
+            (start.elapsed(), counts)
+        };
+        let (by_round, expected) = time(&|| simulate_by_round::<u64>(&monkeys, &rules));
+        let (by_item, counts) = time(&|| super::simulate::<u64>(&monkeys, &rules).unwrap());
+        println!("round by round: {by_round:?}, item by item: {by_item:?}");
+        assert_eq!(expected, counts);
+    }
+
+    #[test]
+    fn reduction() {
+        let monkeys = super::parse_monkeys(&example(11, 1)).unwrap();
+        assert_eq!(Some(96577), super::modulus(&monkeys, &Rules::PART_TWO));
+        let relief = Rules {
+            reduce: true,
+            ..Rules::PART_ONE
+        };
+        assert_eq!(None, super::modulus(&monkeys, &relief));
+
+        // reducing `old * 5 - 3` would change where items go, so it isn't
+        let input = example(11, 1).replace("new = old * old", "new = old * 5 - 3");
+        let monkeys = super::parse_monkeys(&input).unwrap();
+        let rules = Rules {
+            rounds: 100,
+            ..Rules::PART_TWO
+        };
+        assert_eq!(None, super::modulus(&monkeys, &rules));
+        let reduced = super::simulate::<BigUint>(&monkeys, &rules).unwrap();
+        let rules = Rules {
+            reduce: false,
+            ..rules
+        };
+        assert_eq!(
+            reduced,
+            super::simulate::<BigUint>(&monkeys, &rules).unwrap()
+        );
+    }
+
+    #[test]
+    fn undefined_worry() {
+        let input = example(11, 1).replace("new = old * 19", "new = old - 100");
+        let monkeys = super::parse_monkeys(&input).unwrap();
+        let err = super::simulate::<u64>(&monkeys, &Rules::PART_TWO).unwrap_err();
+        let expected = WorryError {
+            round: 1,
+            monkey: 0,
+            fault: Fault::Undefined,
+        };
+        assert_eq!(expected, err);
+        assert_eq!(
+            "day 11: the operation on line 3 goes negative or divides by zero in round 1",
+            super::one(&input).unwrap_err().to_string()
+        );
+
+        let input = example(11, 1).replace("new = old + 6", "new = old / (old - 60)");
+        let err = super::two(&input).unwrap_err();
+        assert!(err.to_string().contains("line 10"), "{}", err);
+    }
+
+    #[test]
+    fn parse_error() {
+        let input = example(11, 1).replace("Test: divisible by 19", "Test: divisable by 19");
+        let err = super::parse_monkeys(&input).unwrap_err();
+        assert_eq!(
+            "day 11, line 11: expected `Test: divisible by`",
+            err.to_string()
+        );
+
+        let input = example(11, 1).replace("new = old * 19", "new = old * (19");
+        let err = super::parse_monkeys(&input).unwrap_err();
+        assert_eq!((3, Some(29)), (err.line, err.column));
     }
 }
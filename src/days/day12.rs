@@ -3,12 +3,14 @@ use std::{
     fmt::Display,
 };
 
-use crate::utils::grid::{Direction, Grid, Point2};
+use crate::{
+    parse::ParseError,
+    utils::grid::{Direction, Grid, Point2},
+};
 
 #[derive(Clone, Copy, Debug)]
-struct Position {
+pub(crate) struct Position {
     elevation: u8,
-    start: bool,
     end: bool,
 }
 
@@ -26,14 +28,14 @@ struct Path {
 }
 
 #[allow(dead_code)]
-pub fn one(input: &str) -> usize {
-    let (grid, start, _) = parse(input);
-    find_trail(&grid, start).unwrap()
+pub fn one(input: &str) -> Result<usize, ParseError> {
+    let (grid, start, _) = parse(input)?;
+    Ok(find_trail(&grid, start).unwrap())
 }
 
 #[allow(dead_code)]
-pub fn two(input: &str) -> usize {
-    let (grid, _, _) = parse(input);
+pub fn two(input: &str) -> Result<usize, ParseError> {
+    let (grid, _, _) = parse(input)?;
     let mut steps = usize::MAX;
     let starting_squares: Vec<Point2> = grid
         .map
@@ -48,13 +50,13 @@ pub fn two(input: &str) -> usize {
         }
     }
 
-    steps
+    Ok(steps)
 }
 
 fn find_trail(grid: &Grid<Position>, start: Point2) -> Option<usize> {
     let offsets = Direction::cardinals().map(|d| d.offset());
     let mut queue = VecDeque::new();
-    for o in offsets.clone() {
+    for o in offsets {
         queue.push_back(Path {
             prev: start,
             cur: start + o,
@@ -86,7 +88,7 @@ fn find_trail(grid: &Grid<Position>, start: Point2) -> Option<usize> {
             return Some(path.count);
         }
 
-        for o in offsets.clone() {
+        for o in offsets {
             queue.push_back(Path {
                 prev: path.cur,
                 cur: path.cur + o,
@@ -98,47 +100,59 @@ fn find_trail(grid: &Grid<Position>, start: Point2) -> Option<usize> {
     None
 }
 
-fn parse(input: &str) -> (Grid<Position>, Point2, Point2) {
-    let input = input
-        .lines()
-        .map(|l| {
-            l.chars()
-                .map(|c| {
-                    Some(Position {
-                        start: c == 'S',
-                        end: c == 'E',
-                        elevation: match c {
-                            'a'..='z' => c as u8 - 97,
-                            'S' => b'a' - 97,
-                            'E' => b'z' - 97,
-                            _ => panic!("input"),
-                        },
-                    })
-                })
-                .collect()
+pub(crate) fn parse(input: &str) -> Result<(Grid<Position>, Point2, Point2), ParseError> {
+    let (grid, marks) = Grid::parse_marked(input, &['S', 'E'], |c| {
+        let elevation = match c {
+            'a'..='z' => c as u8 - b'a',
+            'S' => 0,
+            'E' => b'z' - b'a',
+            _ => return Err("a letter"),
+        };
+        Ok(Some(Position {
+            end: c == 'E',
+            elevation,
+        }))
+    })
+    .map_err(|e| ParseError::from_grid(12, e))?;
+
+    let mark = |c| {
+        marks.get(&c).copied().ok_or_else(|| {
+            ParseError::new(
+                12,
+                grid.height + 1,
+                format!("a `{}` somewhere in the map", c),
+            )
         })
-        .collect();
-
-    let grid = Grid::from_vec(input);
-    let (start, _) = grid.find(|x| x.start).unwrap();
-    let (end, _) = grid.find(|x| x.end).unwrap();
-
-    (grid, start, end)
+    };
+    let (start, end) = (mark('S')?, mark('E')?);
+    Ok((grid, start, end))
 }
 
 #[cfg(test)]
 mod tests {
-    const INPUT: &str = "src/input/12.txt";
+    use crate::inputs::{example, real};
 
     #[test]
     fn one() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(350, super::one(&input));
+        let input = real(12);
+        assert_eq!(Ok(350), super::one(&input));
     }
 
     #[test]
     fn two() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(349, super::two(&input));
+        let input = real(12);
+        assert_eq!(Ok(349), super::two(&input));
+    }
+
+    #[test]
+    fn one_example() {
+        let input = example(12, 1);
+        assert_eq!(Ok(31), super::one(&input));
+    }
+
+    #[test]
+    fn two_example() {
+        let input = example(12, 1);
+        assert_eq!(Ok(29), super::two(&input));
     }
 }
@@ -1,23 +1,26 @@
-use std::{cmp::Ordering, str::FromStr};
+use std::{cmp::Ordering, fmt::Display, ops::Range, str::FromStr};
 
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::parse::ParseError;
 
 #[allow(dead_code)]
-pub fn one(input: &str) -> usize {
-    let input = parse_input(input);
-    input
+pub fn one(input: &str) -> Result<usize, ParseError> {
+    let input = parse_input(input)?;
+    Ok(input
         .into_iter()
         .tuples()
         .enumerate()
         .map(|(i, (l, r))| if l < r { i + 1 } else { 0 })
-        .sum()
+        .sum())
 }
 
 #[allow(dead_code)]
-pub fn two(input: &str) -> usize {
-    let mut input = parse_input(input);
+pub fn two(input: &str) -> Result<usize, ParseError> {
+    let mut input = parse_input(input)?;
 
-    let dividers = vec!["[[2]]".parse().unwrap(), "[[6]]".parse().unwrap()];
+    let dividers = vec![packet![[2]], packet![[6]]];
     input.append(&mut dividers.clone());
 
     input.sort();
@@ -29,15 +32,36 @@ pub fn two(input: &str) -> usize {
             .map(|x| x.0 + 1)
             .unwrap()
     });
-    a * b
+    Ok(a * b)
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
-enum Packet {
+/// A packet: an integer or a list of packets, written like `[1,[2,3],[]]`.
+///
+/// [`Display`] writes packets in the same format [`FromStr`] reads, and with serde they map to
+/// JSON integers and arrays, so `[1,[2,3],[]]` is also its own JSON. Build literals with
+/// [`packet!`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Packet {
     Integer(i32),
     List(Vec<Self>),
 }
 
+/// A [`Packet`] literal: `packet![1, [2, 3], []]` is `[1,[2,3],[]]`. Integers can be any
+/// expression that's a single token tree, like `x`, `(x + 1)` or `(-1)`.
+macro_rules! packet {
+    (@item [$($inner:tt)*]) => {
+        $crate::days::day13::packet![$($inner)*]
+    };
+    (@item $integer:expr) => {
+        $crate::days::day13::Packet::Integer($integer)
+    };
+    ($($item:tt),* $(,)?) => {
+        $crate::days::day13::Packet::List(vec![$($crate::days::day13::packet!(@item $item)),*])
+    };
+}
+pub(crate) use packet;
+
 impl Packet {
     fn as_slice(&self) -> &[Self] {
         if let Packet::List(list) = self {
@@ -48,6 +72,15 @@ impl Packet {
     }
 }
 
+impl Display for Packet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Packet::Integer(v) => write!(f, "{}", v),
+            Packet::List(list) => write!(f, "[{}]", list.iter().format(",")),
+        }
+    }
+}
+
 impl PartialOrd for Packet {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -102,44 +135,161 @@ impl Ord for Packet {
     }
 }
 
+/// Why a packet couldn't be read, and where. Spans count characters, not bytes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PacketError {
+    pub span: Range<usize>,
+    pub message: String,
+}
+
+impl Display for PacketError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at {}..{}",
+            self.message, self.span.start, self.span.end
+        )
+    }
+}
+
+impl std::error::Error for PacketError {}
+
 impl FromStr for Packet {
-    type Err = String;
+    type Err = PacketError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         use chumsky::prelude::*;
         fn parser() -> impl Parser<char, Packet, Error = Simple<char>> {
+            let integer = just('-')
+                .or_not()
+                .chain::<char, _, _>(text::digits(10))
+                .collect::<String>()
+                .try_map(|v, span| {
+                    v.parse()
+                        .map(Packet::Integer)
+                        .map_err(|e| Simple::custom(span, format!("{}: `{}`", e, v)))
+                });
+
             recursive(|bf| {
                 bf.separated_by(just(','))
                     .delimited_by(just('['), just(']'))
                     .map(Packet::List)
-                    .or(text::int(10).map(|v: String| Packet::Integer(v.parse().unwrap())))
+                    .or(integer)
                     .padded()
             })
+            .then_ignore(end())
         }
 
-        parser()
-            .parse(s)
-            .map_err(|_| "failed to parse Packet".to_string())
+        parser().parse(s).map_err(|errs| {
+            let err = &errs[0];
+            let message = match err.reason() {
+                chumsky::error::SimpleReason::Custom(message) => message.clone(),
+                _ => err.to_string(),
+            };
+            PacketError {
+                span: err.span(),
+                message,
+            }
+        })
     }
 }
 
-fn parse_input(input: &str) -> Vec<Packet> {
-    input.lines().filter_map(|l| l.parse().ok()).collect()
+pub(crate) fn parse_input(input: &str) -> Result<Vec<Packet>, ParseError> {
+    let mut packets = Vec::new();
+    for (i, l) in input.lines().enumerate() {
+        // pairs of packets are separated by empty lines
+        if !l.is_empty() {
+            let packet = l.parse().map_err(|e: PacketError| {
+                ParseError::new(13, i + 1, "a packet like `[1,[2,3]]`").at_column(e.span.start + 1)
+            })?;
+            packets.push(packet);
+        }
+    }
+    Ok(packets)
 }
 
 #[cfg(test)]
 mod tests {
-    const INPUT: &str = "src/input/13.txt";
+    use super::{Packet, PacketError};
+    use crate::inputs::{example, real};
 
     #[test]
     fn one() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(4643, super::one(&input));
+        let input = real(13);
+        assert_eq!(Ok(4643), super::one(&input));
     }
 
     #[test]
     fn two() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(21614, super::two(&input));
+        let input = real(13);
+        assert_eq!(Ok(21614), super::two(&input));
+    }
+
+    #[test]
+    fn one_example() {
+        let input = example(13, 1);
+        assert_eq!(Ok(13), super::one(&input));
+    }
+
+    #[test]
+    fn two_example() {
+        let input = example(13, 1);
+        assert_eq!(Ok(140), super::two(&input));
+    }
+
+    #[test]
+    fn round_trip() {
+        let input = example(13, 1);
+        for line in input.lines().filter(|l| !l.is_empty()) {
+            let packet: Packet = line.parse().unwrap();
+            assert_eq!(line, packet.to_string());
+            assert_eq!(line, serde_json::to_string(&packet).unwrap());
+            assert_eq!(packet, serde_json::from_str(line).unwrap());
+        }
+
+        let packet = packet![(-1), [2, [3, 4]], []];
+        assert_eq!(Ok(packet.clone()), packet.to_string().parse());
+    }
+
+    #[test]
+    fn macro_literals() {
+        let x = 4;
+        assert_eq!(Packet::List(vec![]), packet![]);
+        assert_eq!(
+            Packet::List(vec![
+                Packet::Integer(1),
+                Packet::List(vec![Packet::Integer(4), Packet::Integer(5)]),
+                Packet::List(vec![Packet::List(vec![])]),
+            ]),
+            packet![1, [x, (x + 1)], [[]],]
+        );
+        assert_eq!(Ok(packet![[1], 2]), " [ [1] , 2 ] ".parse());
+    }
+
+    #[test]
+    fn serde_rejects_other_json() {
+        assert!(serde_json::from_str::<Packet>("[1,\"2\"]").is_err());
+        assert!(serde_json::from_str::<Packet>("[1.5]").is_err());
+        assert!(serde_json::from_str::<Packet>("{}").is_err());
+    }
+
+    #[test]
+    fn parse_error() {
+        let err = "[1,2]x".parse::<Packet>().unwrap_err();
+        assert_eq!(5..6, err.span);
+        assert_eq!(
+            "found 'x' but end of input was expected at 5..6",
+            err.to_string()
+        );
+
+        let err: PacketError = "[1,[99999999999]]".parse::<Packet>().unwrap_err();
+        assert_eq!(4..15, err.span);
+        assert!(err.message.contains("too large"), "{}", err);
+
+        let err = super::parse_input("[1,2]\n[3]\n\n[4,,5]\n[6]").unwrap_err();
+        assert_eq!(
+            "day 13, line 4, column 4: expected a packet like `[1,[2,3]]`",
+            err.to_string()
+        );
     }
 }
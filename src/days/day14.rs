@@ -4,49 +4,55 @@ use std::{
     str::FromStr,
 };
 
-use itertools::Itertools;
+use crate::{
+    days::SolveError,
+    parse::{self, ParseError},
+    utils::{
+        grid::{Grid, Point2},
+        render::{Recorder, Rgb, ToRgb},
+    },
+};
 
-use crate::utils::grid::{Grid, Point2};
+#[allow(dead_code)]
+pub fn one(input: &str) -> Result<u32, ParseError> {
+    fill(input, false, &mut Recorder::off())
+}
 
 #[allow(dead_code)]
-pub fn one(input: &str) -> u32 {
-    let (mut grid, floor_y) = parse(input);
-    let mut i = 0;
-    loop {
-        match simulate_sand_corn(&mut grid, floor_y, |grid, p| grid.map.get(&p).is_some()) {
-            SandResult::Abyss => break,
-            SandResult::Settled => i += 1,
-            _ => {}
-        };
-    }
+pub fn two(input: &str) -> Result<u32, ParseError> {
+    fill(input, true, &mut Recorder::off())
+}
 
-    i
+/// Records the cave every time a unit of sand comes to rest in `part`.
+pub(crate) fn record(input: &str, part: usize, recorder: &mut Recorder) -> Result<(), SolveError> {
+    fill(input, part == 2, recorder)
+        .map(|_| ())
+        .map_err(Into::into)
 }
 
-#[allow(dead_code)]
-pub fn two(input: &str) -> u32 {
-    let (mut grid, floor_y) = parse(input);
+/// Pours sand until it falls into the abyss or, with a `floor`, until the source is blocked.
+fn fill(input: &str, floor: bool, recorder: &mut Recorder) -> Result<u32, ParseError> {
+    let (mut grid, floor_y) = parse(input)?;
     let mut i = 0;
+    recorder.record(&grid);
     loop {
         match simulate_sand_corn(&mut grid, floor_y, |grid, p| {
-            if let Some(_) = grid.map.get(&p) {
-                true
-            } else {
-                p.y == floor_y
-            }
+            grid.map.contains_key(&p) || (floor && p.y == floor_y)
         }) {
-            SandResult::Abyss => panic!("can't happen"),
-            SandResult::Settled => i += 1,
-            SandResult::Congested => break,
+            SandResult::Abyss | SandResult::Congested => break,
+            SandResult::Settled(p) => {
+                i += 1;
+                recorder.record_changes([(p, Some(Tile::Sand.to_rgb()))]);
+            }
         }
     }
 
-    i
+    Ok(i)
 }
 
 enum SandResult {
     Abyss,
-    Settled,
+    Settled(Point2),
     Congested,
 }
 
@@ -61,22 +67,22 @@ where
         Point2 { x: 1, y: 1 },
     );
     loop {
-        if !obstacle(&grid, sand + down) {
+        if !obstacle(grid, sand + down) {
             sand = sand + down;
             if sand.y > floor_y {
                 break SandResult::Abyss;
             }
-        } else if !obstacle(&grid, sand + down_left) {
+        } else if !obstacle(grid, sand + down_left) {
             sand = sand + down_left;
-        } else if !obstacle(&grid, sand + down_right) {
+        } else if !obstacle(grid, sand + down_right) {
             sand = sand + down_right;
         } else {
-            if obstacle(&grid, sand) {
+            if obstacle(grid, sand) {
                 break SandResult::Congested;
             }
 
             grid.map.insert(sand, Tile::Sand);
-            break SandResult::Settled;
+            break SandResult::Settled(sand);
         }
     }
 }
@@ -85,26 +91,33 @@ where
 struct Path(Vec<Point2>);
 
 impl FromStr for Path {
-    type Err = String;
+    type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let points = s
             .split(" -> ")
             .map(|s| {
-                s.split_once(',')
-                    .map(|(a, b)| Point2 {
-                        x: a.parse().unwrap(),
-                        y: b.parse().unwrap(),
-                    })
-                    .unwrap()
+                let (x, y) = s.split_once(',').ok_or(())?;
+                Ok(Point2 {
+                    x: x.parse().map_err(|_| ())?,
+                    y: y.parse().map_err(|_| ())?,
+                })
             })
-            .collect();
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // rocks only form horizontal and vertical lines
+        if points
+            .windows(2)
+            .any(|p| p[0].x != p[1].x && p[0].y != p[1].y)
+        {
+            return Err(());
+        }
         Ok(Path(points))
     }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-enum Tile {
+pub(crate) enum Tile {
     Rock,
     Sand,
 }
@@ -118,11 +131,25 @@ impl Display for Tile {
     }
 }
 
-fn parse(input: &str) -> (Grid<Tile>, isize) {
-    let paths = input
-        .lines()
-        .map(|l| l.parse::<Path>().unwrap())
-        .collect_vec();
+impl ToRgb for Tile {
+    fn to_rgb(&self) -> Rgb {
+        match self {
+            Tile::Rock => Rgb(120, 110, 100),
+            Tile::Sand => Rgb(230, 200, 120),
+        }
+    }
+}
+
+pub(crate) fn parse(input: &str) -> Result<(Grid<Tile>, isize), ParseError> {
+    let paths = parse::lines(
+        14,
+        input,
+        "a path of straight lines like `498,4 -> 498,6 -> 496,6`",
+        |l| l.parse::<Path>().ok(),
+    )?;
+    if paths.is_empty() {
+        return Err(ParseError::new(14, 1, "at least one path"));
+    }
 
     let mut all_points = HashSet::new();
     for path in paths {
@@ -140,15 +167,15 @@ fn parse(input: &str) -> (Grid<Tile>, isize) {
 
     let floor = floor_y(&g);
 
-    (g, floor)
+    Ok((g, floor))
 }
 
 fn points_between(p1: Point2, p2: Point2) -> Vec<Point2> {
     let mut points = Vec::new();
     let mut p = p1;
     while p != p2 {
-        p.x = p.x + (p2.x - p1.x).signum();
-        p.y = p.y + (p2.y - p1.y).signum();
+        p.x += (p2.x - p1.x).signum();
+        p.y += (p2.y - p1.y).signum();
         points.push(p);
     }
     points
@@ -166,17 +193,29 @@ fn floor_y(grid: &Grid<Tile>) -> isize {
 
 #[cfg(test)]
 mod tests {
-    const INPUT: &str = "src/input/14.txt";
+    use crate::inputs::{example, real};
 
     #[test]
     fn one() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(817, super::one(&input));
+        let input = real(14);
+        assert_eq!(Ok(817), super::one(&input));
     }
 
     #[test]
     fn two() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(23416, super::two(&input));
+        let input = real(14);
+        assert_eq!(Ok(23416), super::two(&input));
+    }
+
+    #[test]
+    fn one_example() {
+        let input = example(14, 1);
+        assert_eq!(Ok(24), super::one(&input));
+    }
+
+    #[test]
+    fn two_example() {
+        let input = example(14, 1);
+        assert_eq!(Ok(93), super::two(&input));
     }
 }
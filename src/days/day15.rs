@@ -0,0 +1,162 @@
+use std::collections::HashSet;
+
+use itertools::Itertools;
+use scan_fmt::scan_fmt;
+
+use crate::{
+    days::SolveError,
+    parse::{self, ParseError},
+    utils::grid::Point2,
+};
+
+#[allow(dead_code)]
+pub fn one(input: &str) -> Result<usize, ParseError> {
+    let sensors = parse(input)?;
+    Ok(excluded_in_row(&sensors, 2_000_000))
+}
+
+#[allow(dead_code)]
+pub fn two(input: &str) -> Result<i64, SolveError> {
+    let sensors = parse(input)?;
+    let beacon = find_distress_beacon(&sensors, 4_000_000).ok_or_else(|| {
+        SolveError::unsolvable(15, "the sensors leave no spot for the distress beacon")
+    })?;
+    Ok(beacon.x as i64 * 4_000_000 + beacon.y as i64)
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Sensor {
+    position: Point2,
+    beacon: Point2,
+    range: isize,
+}
+
+impl Sensor {
+    fn covers(&self, p: Point2) -> bool {
+        self.position.manhattan(&p) <= self.range
+    }
+
+    /// The inclusive range of x coordinates this sensor covers in row `y`, if any.
+    fn coverage_in_row(&self, y: isize) -> Option<(isize, isize)> {
+        let reach = self.range - self.position.y.abs_diff(y) as isize;
+        if reach < 0 {
+            None
+        } else {
+            Some((self.position.x - reach, self.position.x + reach))
+        }
+    }
+}
+
+fn excluded_in_row(sensors: &[Sensor], y: isize) -> usize {
+    let ranges = sensors
+        .iter()
+        .filter_map(|s| s.coverage_in_row(y))
+        .sorted()
+        .collect_vec();
+
+    // merge overlapping ranges
+    let mut merged: Vec<(isize, isize)> = Vec::new();
+    for (from, to) in ranges {
+        match merged.last_mut() {
+            Some(last) if from <= last.1 + 1 => last.1 = last.1.max(to),
+            _ => merged.push((from, to)),
+        }
+    }
+
+    let covered = merged
+        .iter()
+        .map(|(from, to)| from.abs_diff(*to) + 1)
+        .sum::<usize>();
+    let beacons = sensors
+        .iter()
+        .map(|s| s.beacon)
+        .filter(|b| b.y == y)
+        .collect::<HashSet<_>>()
+        .len();
+
+    covered - beacons
+}
+
+/// Finds the only point within `0..=max` on both axes that no sensor covers.
+///
+/// That point has to lie just outside the range of at least two sensors, so rather than scanning
+/// every row we only check where the diagonal lines bordering each sensor's range intersect.
+fn find_distress_beacon(sensors: &[Sensor], max: isize) -> Option<Point2> {
+    let mut ascending = HashSet::new();
+    let mut descending = HashSet::new();
+    for s in sensors {
+        let (x, y, r) = (s.position.x, s.position.y, s.range + 1);
+        // x - y = c
+        ascending.insert(x - y - r);
+        ascending.insert(x - y + r);
+        // x + y = c
+        descending.insert(x + y - r);
+        descending.insert(x + y + r);
+    }
+
+    ascending
+        .iter()
+        .cartesian_product(descending.iter())
+        .filter(|(a, d)| (*a + *d).rem_euclid(2) == 0)
+        .map(|(a, d)| Point2 {
+            x: (a + d) / 2,
+            y: (d - a) / 2,
+        })
+        .filter(|p| (0..=max).contains(&p.x) && (0..=max).contains(&p.y))
+        .find(|p| sensors.iter().all(|s| !s.covers(*p)))
+}
+
+pub(crate) fn parse(input: &str) -> Result<Vec<Sensor>, ParseError> {
+    parse::lines(
+        15,
+        input,
+        "`Sensor at x=N, y=N: closest beacon is at x=N, y=N`",
+        |l| {
+            let (sx, sy, bx, by) = scan_fmt!(
+                l,
+                "Sensor at x={}, y={}: closest beacon is at x={}, y={}",
+                isize,
+                isize,
+                isize,
+                isize
+            )
+            .ok()?;
+            let position = Point2 { x: sx, y: sy };
+            let beacon = Point2 { x: bx, y: by };
+            Some(Sensor {
+                position,
+                beacon,
+                range: position.manhattan(&beacon),
+            })
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{inputs::example, utils::grid::Point2};
+
+    #[test]
+    fn one_example() {
+        let sensors = super::parse(&example(15, 1)).unwrap();
+        assert_eq!(26, super::excluded_in_row(&sensors, 10));
+    }
+
+    #[test]
+    fn two_example() {
+        let sensors = super::parse(&example(15, 1)).unwrap();
+        assert_eq!(
+            Some(Point2 { x: 14, y: 11 }),
+            super::find_distress_beacon(&sensors, 20)
+        );
+    }
+
+    #[test]
+    fn no_gap() {
+        let err = super::two("").unwrap_err();
+        assert_eq!(
+            "day 15: the sensors leave no spot for the distress beacon",
+            err.to_string()
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+
+use scan_fmt::scan_fmt;
+
+use crate::parse::{self, ParseError};
+
+#[allow(dead_code)]
+pub fn one(input: &str) -> Result<u32, ParseError> {
+    let network = Network::new(&parse(input)?);
+    Ok(network.best_per_set(30).into_iter().max().unwrap())
+}
+
+#[allow(dead_code)]
+pub fn two(input: &str) -> Result<u32, ParseError> {
+    let network = Network::new(&parse(input)?);
+    let best = network.best_per_set(26);
+
+    // best pressure achievable by opening any subset of each set of valves
+    let mut best_subset = best.clone();
+    for bit in 0..network.flows.len() {
+        for set in 0..best_subset.len() {
+            if set & (1 << bit) != 0 {
+                best_subset[set] = best_subset[set].max(best_subset[set ^ (1 << bit)]);
+            }
+        }
+    }
+
+    // you and the elephant open disjoint sets of valves
+    let all = best.len() - 1;
+    Ok(best
+        .iter()
+        .enumerate()
+        .map(|(set, pressure)| pressure + best_subset[all ^ set])
+        .max()
+        .unwrap())
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Valve {
+    name: String,
+    flow: u32,
+    tunnels: Vec<String>,
+}
+
+/// The valves worth opening, with the travel time between each of them.
+struct Network {
+    flows: Vec<u32>,
+    /// `distances[i][j]` is the time it takes to walk from valve `i` to valve `j`. The last index
+    /// is the starting valve `AA`.
+    distances: Vec<Vec<u32>>,
+}
+
+impl Network {
+    fn new(valves: &[Valve]) -> Self {
+        let index = valves
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (v.name.as_str(), i))
+            .collect::<HashMap<_, _>>();
+
+        // all-pairs shortest paths
+        let n = valves.len();
+        let mut dist = vec![vec![u32::MAX / 2; n]; n];
+        for (i, v) in valves.iter().enumerate() {
+            dist[i][i] = 0;
+            for t in &v.tunnels {
+                dist[i][index[t.as_str()]] = 1;
+            }
+        }
+        for k in 0..n {
+            for i in 0..n {
+                for j in 0..n {
+                    dist[i][j] = dist[i][j].min(dist[i][k] + dist[k][j]);
+                }
+            }
+        }
+
+        let mut relevant = (0..n).filter(|i| valves[*i].flow > 0).collect::<Vec<_>>();
+        relevant.push(index["AA"]);
+
+        Network {
+            flows: relevant[..relevant.len() - 1]
+                .iter()
+                .map(|i| valves[*i].flow)
+                .collect(),
+            distances: relevant
+                .iter()
+                .map(|i| relevant.iter().map(|j| dist[*i][*j]).collect())
+                .collect(),
+        }
+    }
+
+    /// For every set of opened valves (as a bitmask), the most pressure that can be released
+    /// within `minutes` by opening exactly those valves.
+    fn best_per_set(&self, minutes: u32) -> Vec<u32> {
+        let mut best = vec![0; 1 << self.flows.len()];
+        self.visit(self.flows.len(), minutes, 0, 0, &mut best);
+        best
+    }
+
+    fn visit(&self, at: usize, minutes: u32, opened: usize, pressure: u32, best: &mut [u32]) {
+        best[opened] = best[opened].max(pressure);
+
+        for (next, flow) in self.flows.iter().enumerate() {
+            let cost = self.distances[at][next] + 1;
+            if opened & (1 << next) != 0 || cost >= minutes {
+                continue;
+            }
+            let left = minutes - cost;
+            self.visit(
+                next,
+                left,
+                opened | (1 << next),
+                pressure + left * flow,
+                best,
+            );
+        }
+    }
+}
+
+pub(crate) fn parse(input: &str) -> Result<Vec<Valve>, ParseError> {
+    let valves = parse::lines(
+        16,
+        input,
+        "`Valve AA has flow rate=N; tunnels lead to valves BB, CC`",
+        |l| {
+            let (valve, tunnels) = l.split_once("; ")?;
+            let (name, flow) = scan_fmt!(valve, "Valve {} has flow rate={}", String, u32).ok()?;
+            // "tunnels lead to valves A, B" or "tunnel leads to valve A"
+            let tunnels = tunnels
+                .split_whitespace()
+                .skip(4)
+                .map(|t| t.trim_end_matches(',').to_owned())
+                .collect();
+            Some(Valve {
+                name,
+                flow,
+                tunnels,
+            })
+        },
+    )?;
+
+    // every tunnel has to lead somewhere, and there has to be a valve to start from
+    for (i, valve) in valves.iter().enumerate() {
+        if let Some(t) = valve
+            .tunnels
+            .iter()
+            .find(|t| !valves.iter().any(|v| v.name == **t))
+        {
+            return Err(ParseError::new(16, i + 1, format!("a valve named `{}`", t)));
+        }
+    }
+    if !valves.iter().any(|v| v.name == "AA") {
+        return Err(ParseError::new(16, valves.len() + 1, "a valve named `AA`"));
+    }
+
+    Ok(valves)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::inputs::example;
+
+    #[test]
+    fn one_example() {
+        let input = example(16, 1);
+        assert_eq!(Ok(1651), super::one(&input));
+    }
+
+    #[test]
+    fn two_example() {
+        let input = example(16, 1);
+        assert_eq!(Ok(1707), super::two(&input));
+    }
+}
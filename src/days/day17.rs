@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use crate::parse::ParseError;
+
+#[allow(dead_code)]
+pub fn one(input: &str) -> Result<usize, ParseError> {
+    Ok(tower_height(&parse(input)?, 2022))
+}
+
+#[allow(dead_code)]
+pub fn two(input: &str) -> Result<usize, ParseError> {
+    Ok(tower_height(&parse(input)?, 1_000_000_000_000))
+}
+
+const WIDTH: usize = 7;
+
+/// Rows of each rock from the bottom up, with bit `x` set if column `x` is occupied.
+const ROCKS: [&[u8]; 5] = [
+    &[0b1111],
+    &[0b010, 0b111, 0b010],
+    &[0b111, 0b100, 0b100],
+    &[0b1, 0b1, 0b1, 0b1],
+    &[0b11, 0b11],
+];
+
+/// How many of the topmost rows are compared to detect that the simulation repeats.
+const PROFILE_ROWS: usize = 32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Jet {
+    Left,
+    Right,
+}
+
+struct Chamber<'a> {
+    rows: Vec<u8>,
+    jets: &'a [Jet],
+    jet: usize,
+    rock: usize,
+}
+
+impl<'a> Chamber<'a> {
+    fn new(jets: &'a [Jet]) -> Self {
+        Chamber {
+            rows: Vec::new(),
+            jets,
+            jet: 0,
+            rock: 0,
+        }
+    }
+
+    fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn collides(&self, rock: &[u8], y: usize) -> bool {
+        rock.iter()
+            .enumerate()
+            .any(|(i, row)| self.rows.get(y + i).is_some_and(|r| r & row != 0))
+    }
+
+    fn drop_rock(&mut self) {
+        let mut rock = ROCKS[self.rock].iter().map(|r| r << 2).collect::<Vec<_>>();
+        self.rock = (self.rock + 1) % ROCKS.len();
+        let mut y = self.height() + 3;
+
+        loop {
+            // get pushed by a jet of gas
+            let pushed = match self.jets[self.jet] {
+                Jet::Left if rock.iter().all(|r| r & 1 == 0) => {
+                    Some(rock.iter().map(|r| r >> 1).collect::<Vec<_>>())
+                }
+                Jet::Right if rock.iter().all(|r| r & (1 << (WIDTH - 1)) == 0) => {
+                    Some(rock.iter().map(|r| r << 1).collect::<Vec<_>>())
+                }
+                _ => None,
+            };
+            self.jet = (self.jet + 1) % self.jets.len();
+            if let Some(pushed) = pushed {
+                if !self.collides(&pushed, y) {
+                    rock = pushed;
+                }
+            }
+
+            // fall down
+            if y == 0 || self.collides(&rock, y - 1) {
+                break;
+            }
+            y -= 1;
+        }
+
+        for (i, row) in rock.into_iter().enumerate() {
+            if y + i == self.rows.len() {
+                self.rows.push(0);
+            }
+            self.rows[y + i] |= row;
+        }
+    }
+
+    fn profile(&self) -> Vec<u8> {
+        self.rows.iter().rev().take(PROFILE_ROWS).copied().collect()
+    }
+}
+
+/// Drops `rocks` rocks and returns the height of the resulting tower.
+///
+/// Once the chamber is in a state it has been in before (same rock, same jet and same shape at
+/// the top), the rocks dropped since then will keep repeating, so whole cycles are skipped.
+fn tower_height(jets: &[Jet], rocks: usize) -> usize {
+    let mut chamber = Chamber::new(jets);
+    let mut seen = HashMap::new();
+    let mut skipped_height = 0;
+
+    let mut dropped = 0;
+    while dropped < rocks {
+        chamber.drop_rock();
+        dropped += 1;
+
+        if skipped_height > 0 || chamber.height() < PROFILE_ROWS {
+            continue;
+        }
+
+        let key = (chamber.rock, chamber.jet, chamber.profile());
+        if let Some((prev_dropped, prev_height)) = seen.insert(key, (dropped, chamber.height())) {
+            let cycle_len = dropped - prev_dropped;
+            let cycles = (rocks - dropped) / cycle_len;
+            dropped += cycles * cycle_len;
+            skipped_height = cycles * (chamber.height() - prev_height);
+        }
+    }
+
+    chamber.height() + skipped_height
+}
+
+pub(crate) fn parse(input: &str) -> Result<Vec<Jet>, ParseError> {
+    let line = input.lines().next().unwrap_or_default();
+    let jets = line
+        .chars()
+        .enumerate()
+        .map(|(i, c)| match c {
+            '<' => Ok(Jet::Left),
+            '>' => Ok(Jet::Right),
+            _ => Err(ParseError::new(17, 1, "`<` or `>`").at_column(i + 1)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if jets.is_empty() {
+        return Err(ParseError::new(17, 1, "a jet pattern"));
+    }
+    Ok(jets)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::inputs::example;
+
+    #[test]
+    fn one_example() {
+        let input = example(17, 1);
+        assert_eq!(Ok(3068), super::one(&input));
+    }
+
+    #[test]
+    fn two_example() {
+        let input = example(17, 1);
+        assert_eq!(Ok(1514285714288), super::two(&input));
+    }
+}
@@ -1,17 +1,20 @@
 use std::collections::{HashMap, HashSet};
 
-use crate::utils::grid::{BoundingBox3, Point3};
+use crate::{
+    parse::{self, ParseError},
+    utils::grid::{BoundingBox3, Point3},
+};
 
 #[allow(dead_code)]
-pub fn one(input: &str) -> usize {
-    let points = parse_input(input);
+pub fn one(input: &str) -> Result<usize, ParseError> {
+    let points = parse_input(input)?;
     let points = HashSet::<_>::from_iter(points);
-    surface_area(&points)
+    Ok(surface_area(&points))
 }
 
 #[allow(dead_code)]
-pub fn two(input: &str) -> usize {
-    let points = parse_input(input);
+pub fn two(input: &str) -> Result<usize, ParseError> {
+    let points = parse_input(input)?;
     let bb = BoundingBox3::from_points(&points);
     let inverted = invert_points(&points, bb);
 
@@ -23,10 +26,9 @@ pub fn two(input: &str) -> usize {
 
     // subtract the surface area of the air pockets
     let total_surface_area = surface_area(&HashSet::from_iter(points.clone()));
-    let air_pockets_area = clusters.iter().map(|c| surface_area(c)).sum::<usize>();
-    let area = total_surface_area - air_pockets_area;
+    let air_pockets_area = clusters.iter().map(surface_area).sum::<usize>();
 
-    area
+    Ok(total_surface_area - air_pockets_area)
 }
 
 fn surface_area(points: &HashSet<Point3>) -> usize {
@@ -96,31 +98,40 @@ fn neighbors_of(p: Point3) -> [Point3; 6] {
     .map(|o| p + o)
 }
 
-fn parse_input(input: &str) -> Vec<Point3> {
+pub(crate) fn parse_input(input: &str) -> Result<Vec<Point3>, ParseError> {
     use scan_fmt::scan_fmt;
 
-    input
-        .lines()
-        .map(|l| {
-            let (x, y, z) = scan_fmt!(l, "{},{},{}", isize, isize, isize).unwrap();
-            Point3 { x, y, z }
-        })
-        .collect()
+    parse::lines(18, input, "a cube like `2,2,2`", |l| {
+        let (x, y, z) = scan_fmt!(l, "{},{},{}", isize, isize, isize).ok()?;
+        Some(Point3 { x, y, z })
+    })
 }
 
 #[cfg(test)]
 mod tests {
-    const INPUT: &str = "src/input/18.txt";
+    use crate::inputs::{example, real};
 
     #[test]
     fn one() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(3498, super::one(&input));
+        let input = real(18);
+        assert_eq!(Ok(3498), super::one(&input));
     }
 
     #[test]
     fn two() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(2008, super::two(&input));
+        let input = real(18);
+        assert_eq!(Ok(2008), super::two(&input));
+    }
+
+    #[test]
+    fn one_example() {
+        let input = example(18, 1);
+        assert_eq!(Ok(64), super::one(&input));
+    }
+
+    #[test]
+    fn two_example() {
+        let input = example(18, 1);
+        assert_eq!(Ok(58), super::two(&input));
     }
 }
@@ -0,0 +1,137 @@
+use scan_fmt::scan_fmt;
+
+use crate::parse::{self, ParseError};
+
+#[allow(dead_code)]
+pub fn one(input: &str) -> Result<u32, ParseError> {
+    Ok(parse(input)?.iter().map(|b| b.id * max_geodes(b, 24)).sum())
+}
+
+#[allow(dead_code)]
+pub fn two(input: &str) -> Result<u32, ParseError> {
+    Ok(parse(input)?
+        .iter()
+        .take(3)
+        .map(|b| max_geodes(b, 32))
+        .product())
+}
+
+// indices into resource and robot arrays
+const ORE: usize = 0;
+const CLAY: usize = 1;
+const OBSIDIAN: usize = 2;
+const GEODE: usize = 3;
+
+#[derive(Clone, Debug)]
+pub(crate) struct Blueprint {
+    id: u32,
+    /// `costs[robot][resource]`
+    costs: [[u32; 4]; 4],
+}
+
+#[derive(Clone, Copy, Debug)]
+struct State {
+    minutes: u32,
+    resources: [u32; 4],
+    robots: [u32; 4],
+}
+
+fn max_geodes(blueprint: &Blueprint, minutes: u32) -> u32 {
+    // there is no point in producing more of a resource per minute than can be spent per minute
+    let mut max_robots = [u32::MAX; 4];
+    for resource in [ORE, CLAY, OBSIDIAN] {
+        max_robots[resource] = blueprint.costs.iter().map(|c| c[resource]).max().unwrap();
+    }
+
+    let start = State {
+        minutes,
+        resources: [0; 4],
+        robots: [1, 0, 0, 0],
+    };
+    let mut best = 0;
+    search(blueprint, &max_robots, start, &mut best);
+    best
+}
+
+/// Depth-first search that, instead of deciding what to do every minute, decides which robot to
+/// build next and skips ahead to the minute it's done.
+fn search(blueprint: &Blueprint, max_robots: &[u32; 4], state: State, best: &mut u32) {
+    let geodes = state.resources[GEODE] + state.robots[GEODE] * state.minutes;
+    *best = (*best).max(geodes);
+
+    // even building a geode robot every remaining minute wouldn't beat the best so far
+    let t = state.minutes;
+    if geodes + t * t.saturating_sub(1) / 2 <= *best {
+        return;
+    }
+
+    for robot in [GEODE, OBSIDIAN, CLAY, ORE] {
+        if state.robots[robot] >= max_robots[robot] {
+            continue;
+        }
+
+        let cost = &blueprint.costs[robot];
+        let wait = (0..4)
+            .map(|r| {
+                if cost[r] <= state.resources[r] {
+                    Some(0)
+                } else if state.robots[r] == 0 {
+                    None
+                } else {
+                    Some((cost[r] - state.resources[r]).div_ceil(state.robots[r]))
+                }
+            })
+            .try_fold(0, |acc, w| w.map(|w| acc.max(w)));
+
+        // the robot also takes a minute to build, and has to be ready with time left to use it
+        let elapsed = match wait {
+            Some(w) if w + 1 < state.minutes => w + 1,
+            _ => continue,
+        };
+
+        let mut next = state;
+        next.minutes -= elapsed;
+        for (r, amount) in next.resources.iter_mut().enumerate() {
+            *amount = *amount + state.robots[r] * elapsed - cost[r];
+        }
+        next.robots[robot] += 1;
+        search(blueprint, max_robots, next, best);
+    }
+}
+
+pub(crate) fn parse(input: &str) -> Result<Vec<Blueprint>, ParseError> {
+    parse::lines(19, input, "a blueprint", |l| {
+        let (id, ore, clay, obsidian_ore, obsidian_clay, geode_ore, geode_obsidian) = scan_fmt!(
+            l,
+            "Blueprint {}: Each ore robot costs {} ore. Each clay robot costs {} ore. Each obsidian robot costs {} ore and {} clay. Each geode robot costs {} ore and {} obsidian.",
+            u32, u32, u32, u32, u32, u32, u32
+        )
+        .ok()?;
+        Some(Blueprint {
+            id,
+            costs: [
+                [ore, 0, 0, 0],
+                [clay, 0, 0, 0],
+                [obsidian_ore, obsidian_clay, 0, 0],
+                [geode_ore, 0, geode_obsidian, 0],
+            ],
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::inputs::example;
+
+    #[test]
+    fn one_example() {
+        let input = example(19, 1);
+        assert_eq!(Ok(33), super::one(&input));
+    }
+
+    #[test]
+    fn two_example() {
+        let input = example(19, 1);
+        assert_eq!(Ok(56 * 62), super::two(&input));
+    }
+}
@@ -1,24 +1,26 @@
+use crate::parse::{self, ParseError};
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
-struct Num {
+pub(crate) struct Num {
     value: i64,
     idx: usize,
 }
 
 #[allow(dead_code)]
-pub fn one(input: &str) -> i64 {
-    let mut nums = parse(input);
+pub fn one(input: &str) -> Result<i64, ParseError> {
+    let mut nums = parse(input)?;
     let orig = nums.clone();
 
     for on in orig {
         mix(&mut nums, on);
     }
 
-    eval(&nums)
+    Ok(eval(&nums))
 }
 
 #[allow(dead_code)]
-pub fn two(input: &str) -> i64 {
-    let mut nums = parse(input);
+pub fn two(input: &str) -> Result<i64, ParseError> {
+    let mut nums = parse(input)?;
     nums = nums
         .into_iter()
         .map(|n| Num {
@@ -34,7 +36,7 @@ pub fn two(input: &str) -> i64 {
         }
     }
 
-    eval(&nums)
+    Ok(eval(&nums))
 }
 
 fn mix(nums: &mut Vec<Num>, n: Num) {
@@ -44,7 +46,7 @@ fn mix(nums: &mut Vec<Num>, n: Num) {
     nums.insert(to_idx as usize, n);
 }
 
-fn eval(nums: &Vec<Num>) -> i64 {
+fn eval(nums: &[Num]) -> i64 {
     let zero = nums.iter().position(|n| n.value == 0).unwrap();
     [1000 + zero, 2000 + zero, 3000 + zero]
         .map(|n| n % nums.len())
@@ -53,30 +55,47 @@ fn eval(nums: &Vec<Num>) -> i64 {
         .sum()
 }
 
-fn parse(input: &str) -> Vec<Num> {
-    input
-        .lines()
+pub(crate) fn parse(input: &str) -> Result<Vec<Num>, ParseError> {
+    let values = parse::lines(20, input, "a number", |l| l.parse().ok())?;
+    if !values.contains(&0) {
+        return Err(ParseError::new(
+            20,
+            values.len() + 1,
+            "a `0` somewhere in the file",
+        ));
+    }
+    Ok(values
+        .into_iter()
         .enumerate()
-        .map(|(i, l)| Num {
-            value: l.parse().unwrap(),
-            idx: i,
-        })
-        .collect()
+        .map(|(idx, value)| Num { value, idx })
+        .collect())
 }
 
 #[cfg(test)]
 mod tests {
-    const INPUT: &str = "src/input/20.txt";
+    use crate::inputs::{example, real};
 
     #[test]
     fn one() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(19559, super::one(&input));
+        let input = real(20);
+        assert_eq!(Ok(19559), super::one(&input));
     }
 
     #[test]
     fn two() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(912226207972, super::two(&input));
+        let input = real(20);
+        assert_eq!(Ok(912226207972), super::two(&input));
+    }
+
+    #[test]
+    fn one_example() {
+        let input = example(20, 1);
+        assert_eq!(Ok(3), super::one(&input));
+    }
+
+    #[test]
+    fn two_example() {
+        let input = example(20, 1);
+        assert_eq!(Ok(1623178306), super::two(&input));
     }
 }
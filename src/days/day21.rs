@@ -2,9 +2,11 @@ use std::{collections::HashMap, fmt::Display, str::FromStr};
 
 use itertools::Itertools;
 
+use crate::parse::ParseError;
+
 #[allow(dead_code)]
-pub fn one(input: &str) -> i64 {
-    let monkeys = parse(input);
+pub fn one(input: &str) -> Result<i64, ParseError> {
+    let monkeys = parse(input)?;
     let equations = HashMap::from_iter(monkeys.clone().into_iter().map(|m| (m.name, m.yell)));
 
     let root_m = {
@@ -13,14 +15,14 @@ pub fn one(input: &str) -> i64 {
     };
 
     let op = &root_m.yell.construct_from_system(&equations);
-    let val = op.eval().unwrap();
-    val
+
+    Ok(op.eval().unwrap())
 }
 
 #[allow(dead_code)]
-pub fn two(input: &str) -> i64 {
+pub fn two(input: &str) -> Result<i64, ParseError> {
     let monkeys = {
-        let mut monkeys = parse(input);
+        let mut monkeys = parse(input)?;
         monkeys.remove(monkeys.iter().position(|m| m.name == "humn").unwrap());
         monkeys
     };
@@ -36,8 +38,8 @@ pub fn two(input: &str) -> i64 {
         Expr::Binary(expr) => expr,
         _ => panic!("root op must be binary"),
     };
-    let value = solve1(expr).unwrap();
-    value
+
+    Ok(solve1(expr).unwrap())
 }
 
 /// Solves a single-degree equation system of the form x = y.
@@ -51,7 +53,7 @@ fn solve1(expr: &ExprBinary) -> Option<i64> {
 }
 
 #[derive(Clone, Debug)]
-struct Monkey {
+pub(crate) struct Monkey {
     name: String,
     yell: Expr,
 }
@@ -222,7 +224,7 @@ impl FromStr for Expr {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if let Ok(expr) = s.parse::<i64>().map(|n| Expr::Val(n)) {
+        if let Ok(expr) = s.parse::<i64>().map(Expr::Val) {
             // unary
             Ok(expr)
         } else {
@@ -237,11 +239,11 @@ impl FromStr for Expr {
                         "-" => OpKind::Sub,
                         "/" => OpKind::Div,
                         "*" => OpKind::Mul,
-                        _ => panic!("invalid op"),
+                        _ => return Err("one of `+`, `-`, `*` or `/`".to_string()),
                     };
                     Expr::Binary(ExprBinary { lhs, rhs, op })
                 }
-                _ => panic!("invalid op line"),
+                _ => return Err("a number or an operation like `aaaa + bbbb`".to_string()),
             };
             Ok(expr)
         }
@@ -252,31 +254,57 @@ impl FromStr for Monkey {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (name, rest) = s.split_once(": ").unwrap();
+        let (name, rest) = s
+            .split_once(": ")
+            .ok_or_else(|| "a monkey like `root: pppw + sjmn`".to_string())?;
         Ok(Monkey {
             name: name.to_owned(),
-            yell: rest.parse().unwrap(),
+            yell: rest.parse()?,
         })
     }
 }
 
-fn parse(input: &str) -> Vec<Monkey> {
-    input.lines().map(|l| l.parse().unwrap()).collect()
+pub(crate) fn parse(input: &str) -> Result<Vec<Monkey>, ParseError> {
+    let monkeys = input
+        .lines()
+        .enumerate()
+        .map(|(i, l)| l.parse().map_err(|e| ParseError::new(21, i + 1, e)))
+        .collect::<Result<Vec<Monkey>, _>>()?;
+
+    for name in ["root", "humn"] {
+        if !monkeys.iter().any(|m| m.name == name) {
+            let expected = format!("a monkey named `{}`", name);
+            return Err(ParseError::new(21, monkeys.len() + 1, expected));
+        }
+    }
+    Ok(monkeys)
 }
 
 #[cfg(test)]
 mod tests {
-    const INPUT: &str = "src/input/21.txt";
+    use crate::inputs::{example, real};
 
     #[test]
     fn one() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(353837700405464, super::one(&input));
+        let input = real(21);
+        assert_eq!(Ok(353837700405464), super::one(&input));
     }
 
     #[test]
     fn two() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(3678125408017, super::two(&input));
+        let input = real(21);
+        assert_eq!(Ok(3678125408017), super::two(&input));
+    }
+
+    #[test]
+    fn one_example() {
+        let input = example(21, 1);
+        assert_eq!(Ok(152), super::one(&input));
+    }
+
+    #[test]
+    fn two_example() {
+        let input = example(21, 1);
+        assert_eq!(Ok(301), super::two(&input));
     }
 }
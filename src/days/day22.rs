@@ -1,59 +1,60 @@
-use crate::utils::grid::{Direction, Grid, Point2};
+use crate::{
+    parse::ParseError,
+    utils::grid::{Direction, Grid, Point2, Point3},
+};
 use chumsky::prelude::*;
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::{HashSet, VecDeque},
     fmt::Display,
 };
 
 #[allow(dead_code)]
-pub fn one(input: &str) -> i32 {
-    let (grid, mut instr) = parse(input);
+pub fn one(input: &str) -> Result<i32, ParseError> {
+    let (grid, instr) = parse(input)?;
+    Ok(walk(&grid, instr, |pos, dir| {
+        move_on_map_2d(&grid, pos, dir)
+    }))
+}
+
+#[allow(dead_code)]
+pub fn two(input: &str) -> Result<i32, ParseError> {
+    let (grid, instr) = parse(input)?;
+    let cube =
+        Cube::fold(&grid).ok_or_else(|| ParseError::new(22, 1, "a map that folds into a cube"))?;
+    Ok(walk(&grid, instr, |pos, dir| cube.step(&grid, pos, dir)))
+}
+
+/// Follows the instructions from the leftmost open tile of the top row and returns the final
+/// password. `step` decides where moving one tile forward leads, including how to wrap around.
+fn walk<F>(grid: &Grid<Space>, mut instr: VecDeque<Instr>, step: F) -> i32
+where
+    F: Fn(Point2, Dir) -> (Point2, Dir),
+{
     let x = grid.row(0).into_iter().next().unwrap().0.x;
     let mut pos = Point2 { x, y: 0 };
     let mut dir = Dir::East;
 
-    'outer: while let Some(instr) = instr.pop_front() {
-        eprintln!("{:?}, @{:?} facing {:?}", &instr, &pos, &dir);
+    while let Some(instr) = instr.pop_front() {
         match instr {
             Instr::Fwd(n) => {
                 for _ in 0..n {
-                    pos = match move_2d(&grid, pos, dir) {
-                        Some(p) => p,
-                        None => continue 'outer,
-                    };
-                    eprintln!("pos after move: {:?}", &pos);
+                    let (next, next_dir) = step(pos, dir);
+                    match grid.map.get(&next) {
+                        Some(Space::Empty) => (pos, dir) = (next, next_dir),
+                        Some(Space::Wall) => break,
+                        None => panic!("stepped off the map"),
+                    }
                 }
             }
-            Instr::Turn(turn) => {
-                dir = match (dir, turn) {
-                    (Dir::North, Turn::Right) => Dir::East,
-                    (Dir::North, Turn::Left) => Dir::West,
-                    (Dir::East, Turn::Right) => Dir::South,
-                    (Dir::East, Turn::Left) => Dir::North,
-                    (Dir::South, Turn::Right) => Dir::West,
-                    (Dir::South, Turn::Left) => Dir::East,
-                    (Dir::West, Turn::Right) => Dir::North,
-                    (Dir::West, Turn::Left) => Dir::South,
-                };
-            }
+            Instr::Turn(turn) => dir = dir.turn(turn),
         };
-        eprintln!("pos after {:?}: {:?}", &instr, &pos);
     }
 
     score(pos, dir)
 }
 
-#[allow(dead_code)]
-pub fn two(input: &str) -> i32 {
-    // idea:
-    // - turn into Point3 cloud
-
-    unimplemented!()
-}
-
-fn move_on_map_2d(grid: &Grid<Space>, from: Point2, dir: Dir) -> Option<Point2> {
-    let offset = dir.offset();
-    let next = from + offset;
+fn move_on_map_2d(grid: &Grid<Space>, from: Point2, dir: Dir) -> (Point2, Dir) {
+    let next = from + dir.offset();
 
     // figure out the actual position that `next` points to
     let next = match grid.map.get(&next) {
@@ -66,11 +67,149 @@ fn move_on_map_2d(grid: &Grid<Space>, from: Point2, dir: Dir) -> Option<Point2>
         Some(_) => next,
     };
 
-    // check if we can go there
-    match grid.map.get(&next) {
-        Some(Space::Empty) => Some(next),
-        Some(Space::Wall) => None,
-        None => panic!("can't happen"),
+    (next, dir)
+}
+
+/// Where a face of the net ends up once the net is folded into a cube.
+///
+/// Positions on the cube's surface use doubled coordinates centered on the cube, so that the
+/// center of every tile has odd coordinates along the face and the face itself lies at
+/// `normal * size`.
+#[derive(Clone, Copy, Debug)]
+struct Face {
+    /// Top left corner of the face on the map.
+    corner: Point2,
+    /// Points out of the cube.
+    normal: Point3,
+    /// The direction east on the map points to on this face.
+    east: Point3,
+    /// The direction south on the map points to on this face.
+    south: Point3,
+}
+
+impl Face {
+    fn on_surface(&self, pos: Point2, size: isize) -> Point3 {
+        let local = pos - self.corner;
+        self.normal * size
+            + self.east * (2 * local.x + 1 - size)
+            + self.south * (2 * local.y + 1 - size)
+    }
+
+    fn on_map(&self, pos: Point3, size: isize) -> Point2 {
+        let x = (pos.dot(&self.east) + size - 1) / 2;
+        let y = (pos.dot(&self.south) + size - 1) / 2;
+        self.corner + Point2 { x, y }
+    }
+
+    fn direction(&self, dir: Dir) -> Point3 {
+        match dir {
+            Dir::North => -self.south,
+            Dir::East => self.east,
+            Dir::South => self.south,
+            Dir::West => -self.east,
+        }
+    }
+
+    /// The map direction that `direction` points to on this face.
+    fn dir(&self, direction: Point3) -> Dir {
+        [Dir::North, Dir::East, Dir::South, Dir::West]
+            .into_iter()
+            .find(|d| self.direction(*d) == direction)
+            .unwrap()
+    }
+}
+
+/// The map folded into a cube, which works for any of the 11 nets a cube can be unfolded into.
+struct Cube {
+    size: isize,
+    faces: Vec<Face>,
+}
+
+impl Cube {
+    /// Folds the map into a cube, or returns `None` if it isn't a cube net.
+    fn fold(grid: &Grid<Space>) -> Option<Self> {
+        let size = ((grid.map.len() / 6) as f64).sqrt() as isize;
+        if size == 0 || grid.map.len() != (size * size * 6) as usize {
+            return None;
+        }
+
+        let corners = grid
+            .map
+            .keys()
+            .filter(|p| p.x % size == 0 && p.y % size == 0)
+            .copied()
+            .collect::<HashSet<_>>();
+        let first = *corners.iter().min_by_key(|p| (p.y, p.x))?;
+
+        // walk the net, rolling the cube over the edge into every adjacent face
+        let mut faces = vec![Face {
+            corner: first,
+            normal: Point3 { x: 0, y: 0, z: -1 },
+            east: Point3 { x: 1, y: 0, z: 0 },
+            south: Point3 { x: 0, y: 1, z: 0 },
+        }];
+        let mut queue = VecDeque::from([faces[0]]);
+        while let Some(face) = queue.pop_front() {
+            for dir in [Dir::North, Dir::East, Dir::South, Dir::West] {
+                let corner = face.corner + dir.offset() * size;
+                if !corners.contains(&corner) || faces.iter().any(|f| f.corner == corner) {
+                    continue;
+                }
+
+                let (normal, east, south) = match dir {
+                    Dir::North => (-face.south, face.east, face.normal),
+                    Dir::East => (face.east, -face.normal, face.south),
+                    Dir::South => (face.south, face.east, -face.normal),
+                    Dir::West => (-face.east, face.normal, face.south),
+                };
+                let next = Face {
+                    corner,
+                    normal,
+                    east,
+                    south,
+                };
+                faces.push(next);
+                queue.push_back(next);
+            }
+        }
+
+        // six faces that cover the map, one on each side of the cube
+        let cube = Cube { size, faces };
+        let normals = cube.faces.iter().map(|f| f.normal).collect::<HashSet<_>>();
+        let covered = grid.map.keys().all(|p| cube.try_face_at(*p).is_some());
+        (cube.faces.len() == 6 && normals.len() == 6 && covered).then_some(cube)
+    }
+
+    fn try_face_at(&self, pos: Point2) -> Option<&Face> {
+        self.faces.iter().find(|f| {
+            let local = pos - f.corner;
+            (0..self.size).contains(&local.x) && (0..self.size).contains(&local.y)
+        })
+    }
+
+    fn face_at(&self, pos: Point2) -> &Face {
+        self.try_face_at(pos).unwrap()
+    }
+
+    /// Moves one tile forward over the surface of the cube.
+    fn step(&self, grid: &Grid<Space>, from: Point2, dir: Dir) -> (Point2, Dir) {
+        let next = from + dir.offset();
+        if grid.map.contains_key(&next) {
+            // faces that are adjacent in the net stay adjacent when folded
+            return (next, dir);
+        }
+
+        // go over the edge: one half step forward onto the edge, then one half step down the
+        // face that the edge leads to
+        let face = self.face_at(from);
+        let forward = face.direction(dir);
+        let pos = face.on_surface(from, self.size) + forward - face.normal;
+
+        let next_face = self.faces.iter().find(|f| f.normal == forward).unwrap();
+        (
+            next_face.on_map(pos, self.size),
+            next_face.dir(-face.normal),
+        )
     }
 }
 
@@ -86,18 +225,18 @@ fn score(pos: Point2, dir: Dir) -> i32 {
 }
 
 #[derive(Copy, Clone, Debug)]
-enum Space {
+pub(crate) enum Space {
     Wall,
     Empty,
 }
 
 #[derive(Clone, Copy, Debug)]
-enum Turn {
+pub(crate) enum Turn {
     Right,
     Left,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum Dir {
     North,
     East,
@@ -106,6 +245,19 @@ enum Dir {
 }
 
 impl Dir {
+    fn turn(self, turn: Turn) -> Self {
+        match (self, turn) {
+            (Dir::North, Turn::Right) => Dir::East,
+            (Dir::North, Turn::Left) => Dir::West,
+            (Dir::East, Turn::Right) => Dir::South,
+            (Dir::East, Turn::Left) => Dir::North,
+            (Dir::South, Turn::Right) => Dir::West,
+            (Dir::South, Turn::Left) => Dir::East,
+            (Dir::West, Turn::Right) => Dir::North,
+            (Dir::West, Turn::Left) => Dir::South,
+        }
+    }
+
     pub fn offset(&self) -> Point2 {
         match self {
             Dir::North => Direction::North,
@@ -118,7 +270,7 @@ impl Dir {
 }
 
 #[derive(Clone, Copy, Debug)]
-enum Instr {
+pub(crate) enum Instr {
     Fwd(isize),
     Turn(Turn),
 }
@@ -136,61 +288,145 @@ impl Display for Space {
     }
 }
 
-fn parse(input: &str) -> (Grid<Space>, VecDeque<Instr>) {
+pub(crate) fn parse(input: &str) -> Result<(Grid<Space>, VecDeque<Instr>), ParseError> {
+    let input = input.replace("\r\n", "\n");
+    let (map, pass) = input.split_once("\n\n").ok_or_else(|| {
+        ParseError::new(22, input.lines().count() + 1, "an empty line after the map")
+    })?;
+
     // parse map
-    let grid = input
-        .lines()
-        .take_while(|l| !l.is_empty())
-        .map(|l| {
-            l.chars()
-                .map(|c| match c {
-                    '#' => Some(Space::Wall),
-                    '.' => Some(Space::Empty),
-                    ' ' => None,
-                    _ => panic!("unknown char"),
-                })
-                .collect()
-        })
-        .collect();
+    let grid = Grid::parse(map, |c| match c {
+        '#' => Ok(Some(Space::Wall)),
+        '.' => Ok(Some(Space::Empty)),
+        ' ' => Ok(None),
+        _ => Err("one of `#`, `.` or ` `"),
+    })
+    .map_err(|e| ParseError::from_grid(22, e))?;
+    if grid.row(0).is_empty() {
+        return Err(ParseError::new(22, 1, "a row of the map"));
+    }
 
     // parse instructions
-    let pass = input
-        .lines()
-        .skip_while(|l| !l.is_empty())
-        .skip(1)
-        .next()
-        .unwrap()
-        .to_string();
-
-    let instr_parser = instr_parser();
-    let instr = instr_parser.parse(pass).unwrap().into();
+    let line = map.lines().count() + 2;
+    let instr = instr_parser()
+        .parse(pass.trim_end())
+        .map_err(|errors| {
+            let err = &errors[0];
+            let expected = match err.reason() {
+                chumsky::error::SimpleReason::Custom(message) => message.as_str(),
+                _ => "a number, `L` or `R`",
+            };
+            ParseError::new(22, line, expected).at_column(err.span().start + 1)
+        })?
+        .into();
 
-    (Grid::from_vec(grid), instr)
+    Ok((grid, instr))
 }
 
 fn instr_parser() -> impl Parser<char, Vec<Instr>, Error = Simple<char>> {
     choice((
-        text::int(10).map(|s: String| Instr::Fwd(s.parse().unwrap())),
+        text::int(10).try_map(|s: String, span| {
+            s.parse()
+                .map(Instr::Fwd)
+                .map_err(|_| Simple::custom(span, "a number of steps that fits in an isize"))
+        }),
         just('L').to(Instr::Turn(Turn::Left)),
         just('R').to(Instr::Turn(Turn::Right)),
     ))
     .repeated()
+    .then_ignore(end())
 }
 
 #[cfg(test)]
 mod tests {
-    const INPUT: &str = "src/input/22.txt";
+    use crate::{
+        inputs::{example, real},
+        utils::grid::Grid,
+    };
+
+    use super::{Cube, Dir, Space};
+
+    /// Blows up every `#` of a net into an empty face of the given size.
+    fn net(layout: &str, size: usize) -> Grid<Space> {
+        Grid::from_vec(
+            layout
+                .lines()
+                .flat_map(|l| {
+                    let row = l
+                        .chars()
+                        .flat_map(|c| vec![(c == '#').then_some(Space::Empty); size])
+                        .collect::<Vec<_>>();
+                    vec![row; size]
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn walk_around_cube() {
+        let nets = [
+            "  #\n###\n  ##",
+            " ##\n #\n##\n#",
+            "#\n####\n   #",
+            "###\n  ###",
+            "##\n ###\n   #",
+        ];
+        for layout in nets {
+            let grid = net(layout, 4);
+            let cube = Cube::fold(&grid).unwrap();
+            for &start in grid.map.keys() {
+                for dir in [Dir::North, Dir::East, Dir::South, Dir::West] {
+                    // going straight ahead always leads back to the start after four faces
+                    let (mut pos, mut d) = (start, dir);
+                    for _ in 0..16 {
+                        (pos, d) = cube.step(&grid, pos, d);
+                        assert!(grid.map.contains_key(&pos), "{layout:?} {pos:?}");
+                    }
+                    assert_eq!((start, dir), (pos, d), "{layout:?} from {start:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn not_a_cube() {
+        let input = "...#\n.#..\n\n10R5";
+        assert_eq!(Ok(2013), super::one(input));
+        let err = super::two(input).unwrap_err();
+        assert_eq!(
+            "day 22, line 1: expected a map that folds into a cube",
+            err.to_string()
+        );
+        for layout in ["######", "#\n#\n#\n#\n#\n#", "###\n###", "#"] {
+            assert!(Cube::fold(&net(layout, 2)).is_none(), "{layout:?}");
+        }
+    }
+
+    #[test]
+    fn too_many_steps() {
+        let input = ".\n\n5L99999999999999999999999R1";
+        let err = super::one(input).unwrap_err();
+        assert_eq!(
+            "day 22, line 3, column 3: expected a number of steps that fits in an isize",
+            err.to_string()
+        );
+    }
 
     #[test]
     fn one() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(123046, super::one(&input));
+        let input = real(22);
+        assert_eq!(Ok(123046), super::one(&input));
+    }
+
+    #[test]
+    fn one_example() {
+        let input = example(22, 1);
+        assert_eq!(Ok(6032), super::one(&input));
     }
 
     #[test]
-    #[ignore]
-    fn two() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(0, super::two(&input));
+    fn two_example() {
+        let input = example(22, 1);
+        assert_eq!(Ok(5031), super::two(&input));
     }
 }
@@ -1,134 +1,682 @@
-use std::collections::{HashMap, HashSet};
+use std::{collections::HashSet, fmt::Display};
 
-use itertools::Itertools;
-
-use crate::utils::grid::{BoundingBox2, Direction, Grid, Point2};
+use crate::{
+    days::SolveError,
+    parse::ParseError,
+    utils::{
+        automaton::{Automaton, Boundary, Conflict, Neighbourhood, Neighbours, Rules},
+        grid::{BoundingBox2, Direction, Grid, Point2},
+        render::{Recorder, Rgb},
+    },
+};
 
 #[allow(dead_code)]
-pub fn one(input: &str) -> usize {
-    let mut map = parse(input);
-    let _ = rounds(&mut map, Some(10));
+pub fn one(input: &str) -> Result<usize, ParseError> {
+    let mut map = parse(input)?;
+    let rules = DiffusionRules::part_one();
+    rounds(&mut map, &rules, Backend::Bitboard, &mut Recorder::off())
+        .expect("the puzzle's rules are valid");
     let bounding_box = BoundingBox2::from_points(map.iter().copied());
-    bounding_box.area() - map.len()
+    Ok(bounding_box.area() - map.len())
 }
 
 #[allow(dead_code)]
-pub fn two(input: &str) -> usize {
-    let mut map = parse(input);
-    let rounds_done = rounds(&mut map, None);
-    rounds_done
+pub fn two(input: &str) -> Result<usize, ParseError> {
+    let mut map = parse(input)?;
+    let rules = DiffusionRules::part_two();
+
+    Ok(
+        rounds(&mut map, &rules, Backend::Bitboard, &mut Recorder::off())
+            .expect("the puzzle's rules are valid"),
+    )
 }
 
-fn rounds(map: &mut HashSet<Point2>, nrounds: Option<usize>) -> usize {
-    let mut dir_props = [
-        [Direction::North, Direction::NorthEast, Direction::NorthWest],
-        [Direction::South, Direction::SouthEast, Direction::SouthWest],
-        [Direction::West, Direction::NorthWest, Direction::SouthWest],
-        [Direction::East, Direction::NorthEast, Direction::SouthEast],
-    ];
+/// Records the elves after every round of `part`.
+pub(crate) fn record(input: &str, part: usize, recorder: &mut Recorder) -> Result<(), SolveError> {
+    let mut map = parse(input)?;
+    let rules = if part == 1 {
+        DiffusionRules::part_one()
+    } else {
+        DiffusionRules::part_two()
+    };
+    rounds(&mut map, &rules, Backend::Bitboard, recorder).expect("the puzzle's rules are valid");
+    Ok(())
+}
 
-    let mut i = 0;
-    loop {
-        if let Some(n) = nrounds {
-            if i >= n {
-                break;
+/// How the elves spread out. The puzzle's rules are [`DiffusionRules::part_one`] and
+/// [`DiffusionRules::part_two`].
+#[derive(Clone, Debug)]
+pub(crate) struct DiffusionRules {
+    /// The cardinal directions an elf considers moving in, in order. An elf moves in the first
+    /// one where the point in that direction and the points either side of it are free.
+    pub order: Vec<Direction>,
+    /// Whether the first direction moves to the back of `order` after every round.
+    pub rotate: bool,
+    /// Elves only move if one of these neighbours is occupied. With [`Neighbourhood::None`],
+    /// every elf tries to move.
+    pub neighbourhood: Neighbourhood,
+    /// The elves stop at the first of these that is met. One of them has to be sure to be met,
+    /// see [`DiffusionRules::validate`].
+    pub stop: Vec<Stop>,
+}
+
+/// Why [`DiffusionRules`] can't be played.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum InvalidRules {
+    /// A direction in `order` that isn't north, south, west or east.
+    NotCardinal(Direction),
+    /// Nothing in `stop` is sure to be met.
+    Endless,
+}
+
+impl Display for InvalidRules {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidRules::NotCardinal(dir) => {
+                write!(
+                    f,
+                    "elves can only move in cardinal directions, not {:?}",
+                    dir
+                )
             }
+            InvalidRules::Endless => write!(f, "the elves might never stop"),
         }
+    }
+}
 
-        i += 1;
+impl std::error::Error for InvalidRules {}
 
-        let mut propositions: HashMap<Point2, Vec<Point2>> = HashMap::new();
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Stop {
+    /// After this many rounds.
+    Rounds(usize),
+    /// After a round in which no elf moved.
+    SteadyState,
+    /// Once the smallest rectangle around the elves covers at least this many points.
+    BoundingBox(usize),
+}
 
-        // propose moves - 1st half
-        'inner: for p in map.iter() {
-            let dirs = Direction::all().map(|d| d.offset() + *p);
-            let adjacents = dirs
-                .iter()
-                .filter(|p| map.contains(p))
-                .collect::<HashSet<_>>();
-            if adjacents.len() == 0 {
-                continue 'inner;
-            }
+impl DiffusionRules {
+    pub fn part_one() -> Self {
+        Self {
+            order: vec![
+                Direction::North,
+                Direction::South,
+                Direction::West,
+                Direction::East,
+            ],
+            rotate: true,
+            neighbourhood: Neighbourhood::All,
+            stop: vec![Stop::Rounds(10)],
+        }
+    }
 
-            if let Some(proposed_dir) = dir_props
-                .iter()
-                .filter_map(|dirs| {
-                    let any_occupied = dirs
-                        .map(|d| d.offset() + *p)
-                        .iter()
-                        .any(|p| adjacents.contains(p));
+    pub fn part_two() -> Self {
+        Self {
+            stop: vec![Stop::SteadyState],
+            ..Self::part_one()
+        }
+    }
 
-                    if any_occupied {
-                        None
-                    } else {
-                        Some(dirs[0])
-                    }
-                })
-                .next()
-            {
-                let proposed_tile = proposed_dir.offset() + *p;
-                (*propositions.entry(proposed_tile).or_default()).push(*p);
-            }
+    /// Checks that elves only move in cardinal directions, and that they stop after a number of
+    /// [`Stop::Rounds`]. Only the puzzle's own rules (all four directions, rotating, looking at
+    /// all eight neighbours) are trusted to reach a [`Stop::SteadyState`] without a cap: with
+    /// other rules elves can keep moving forever, e.g. two neighbours that only ever go north.
+    /// A [`Stop::BoundingBox`] on its own might never be reached either.
+    pub fn validate(&self) -> Result<(), InvalidRules> {
+        let cardinal = Neighbourhood::Cardinal.directions();
+        if let Some(dir) = self.order.iter().find(|d| !cardinal.contains(d)) {
+            return Err(InvalidRules::NotCardinal(*dir));
+        }
+        let settles = self.rotate
+            && self.neighbourhood == Neighbourhood::All
+            && cardinal.iter().all(|d| self.order.contains(d));
+        let stops = self.stop.iter().any(|stop| match stop {
+            Stop::Rounds(_) => true,
+            Stop::SteadyState => settles,
+            Stop::BoundingBox(_) => false,
+        });
+        if stops {
+            Ok(())
+        } else {
+            Err(InvalidRules::Endless)
         }
+    }
 
-        // move - 2nd half
-        let moves = propositions
-            .iter()
-            .filter_map(|(p, elves)| {
-                if elves.len() == 1 {
-                    Some((elves[0], *p))
-                } else {
-                    None
-                }
-            })
-            .collect_vec();
+    /// The order of the directions in round `round`, starting at 0.
+    fn order(&self, round: usize) -> impl Iterator<Item = Direction> + '_ {
+        let first = if self.rotate { round } else { 0 };
+        let n = self.order.len();
+        (0..n).map(move |k| self.order[(first + k) % n])
+    }
+}
+
+/// The direction `dir` and the directions either side of it.
+fn with_sides(dir: Direction) -> [Direction; 3] {
+    match dir {
+        Direction::North => [Direction::North, Direction::NorthEast, Direction::NorthWest],
+        Direction::South => [Direction::South, Direction::SouthEast, Direction::SouthWest],
+        Direction::West => [Direction::West, Direction::NorthWest, Direction::SouthWest],
+        Direction::East => [Direction::East, Direction::NorthEast, Direction::SouthEast],
+        _ => panic!("elves only move in cardinal directions"),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Backend {
+    /// The general [`Automaton`], kept as a reference for the bitboard.
+    #[allow(dead_code)]
+    Automaton,
+    Bitboard,
+}
+
+enum Elves {
+    Automaton(Automaton<Diffusion>),
+    Bitboard(Bitboard),
+}
+
+impl Elves {
+    /// Plays a round. Returns whether any elf moved.
+    fn round(&mut self) -> bool {
+        match self {
+            Elves::Automaton(elves) => elves.step() > 0,
+            Elves::Bitboard(elves) => elves.round(),
+        }
+    }
 
-        if moves.is_empty() {
+    fn points(&self) -> Vec<Point2> {
+        match self {
+            Elves::Automaton(elves) => elves.points().collect(),
+            Elves::Bitboard(elves) => elves.points().collect(),
+        }
+    }
+}
+
+/// Plays rounds until one of the rules' stopping conditions is met. Returns the number of rounds
+/// played, or why the rules can't be played.
+fn rounds(
+    map: &mut HashSet<Point2>,
+    rules: &DiffusionRules,
+    backend: Backend,
+    recorder: &mut Recorder,
+) -> Result<usize, InvalidRules> {
+    rules.validate()?;
+    let mut elves = match backend {
+        Backend::Automaton => Elves::Automaton(Automaton::new(
+            Diffusion::new(rules.clone()),
+            map.iter().map(|p| (*p, ())),
+        )),
+        Backend::Bitboard => Elves::Bitboard(Bitboard::new(map, rules)),
+    };
+
+    let mut rounds = 0;
+    loop {
+        recorder.record_with(|| elves.points().into_iter().map(|p| (p, Rgb::WHITE)));
+        let stop = rules.stop.iter().any(|stop| match stop {
+            Stop::Rounds(n) => rounds >= *n,
+            Stop::BoundingBox(area) => BoundingBox2::from_points(elves.points()).area() >= *area,
+            Stop::SteadyState => false,
+        });
+        if stop {
             break;
         }
 
-        for (from, to) in moves {
-            map.remove(&from);
-            map.insert(to);
+        rounds += 1;
+        if !elves.round() && rules.stop.contains(&Stop::SteadyState) {
+            break;
         }
+    }
+    *map = elves.points().into_iter().collect();
+    Ok(rounds)
+}
 
-        // rotate directions
-        dir_props.rotate_left(1);
+/// Elves with a neighbour move in the first free direction of their preferences, unless another
+/// elf wants to move to the same point.
+struct Diffusion {
+    rules: DiffusionRules,
+    round: usize,
+}
+
+impl Diffusion {
+    fn new(rules: DiffusionRules) -> Self {
+        Self { rules, round: 0 }
     }
+}
 
-    i
+impl Rules for Diffusion {
+    type Entity = ();
+
+    fn neighbourhood(&self) -> Neighbourhood {
+        // an elf always looks at all its neighbours to find a free direction
+        Neighbourhood::All
+    }
+
+    fn boundary(&self) -> Boundary {
+        Boundary::Unbounded
+    }
+
+    fn conflict(&self) -> Conflict {
+        Conflict::Cancel
+    }
+
+    fn propose(&self, _: Point2, _: &(), neighbours: Neighbours) -> Option<Direction> {
+        let crowded = match self.rules.neighbourhood {
+            Neighbourhood::None => true,
+            n => n.directions().iter().any(|d| neighbours.occupied(*d)),
+        };
+        if !crowded {
+            return None;
+        }
+        self.rules
+            .order(self.round)
+            .find(|dir| with_sides(*dir).iter().all(|d| !neighbours.occupied(*d)))
+    }
+
+    fn end_step(&mut self) {
+        self.round += 1;
+    }
+}
+
+/// Bit `x` of the result is bit `x - 1` of `row`: whether the point to the west is set.
+fn west(row: &[u64], i: usize) -> u64 {
+    (row[i] << 1) | if i > 0 { row[i - 1] >> 63 } else { 0 }
+}
+
+/// Bit `x` of the result is bit `x + 1` of `row`: whether the point to the east is set.
+fn east(row: &[u64], i: usize) -> u64 {
+    (row[i] >> 1) | row.get(i + 1).map_or(0, |w| w << 63)
 }
 
-fn parse(input: &str) -> HashSet<Point2> {
-    let v = input
-        .lines()
-        .map(|l| {
-            l.chars()
-                .map(|c| match c {
-                    '#' => Some(()),
-                    _ => None,
+/// The elves as one bit per point, so that a round is a handful of bitwise operations on every
+/// 64 points. Bit `b` of word `w` in row `y` is the point `origin + (64 * w + b, y)`.
+///
+/// The board grows as the elves spread out, keeping the outermost rows and columns empty so that
+/// every elf's neighbours are on the board.
+struct Bitboard {
+    origin: Point2,
+    /// Words per row.
+    words: usize,
+    height: usize,
+    bits: Vec<u64>,
+    rules: DiffusionRules,
+    round: usize,
+}
+
+// indices of the proposal masks
+const NORTH: usize = 0;
+const SOUTH: usize = 1;
+const WEST: usize = 2;
+const EAST: usize = 3;
+
+impl Bitboard {
+    /// How many empty rows to add at a time when the elves reach the top or bottom.
+    const GROW_ROWS: usize = 8;
+
+    fn new(points: &HashSet<Point2>, rules: &DiffusionRules) -> Self {
+        let bb = BoundingBox2::from_points(points.iter().copied());
+        let width = bb.lowest.x.abs_diff(bb.highest.x) + 3;
+        let height = bb.lowest.y.abs_diff(bb.highest.y) + 3;
+        let words = width.div_ceil(64);
+        let mut board = Bitboard {
+            origin: bb.lowest - Point2 { x: 1, y: 1 },
+            words,
+            height,
+            bits: vec![0; words * height],
+            rules: rules.clone(),
+            round: 0,
+        };
+        for p in points {
+            board.set(*p);
+        }
+        board
+    }
+
+    fn row(&self, y: usize) -> &[u64] {
+        &self.bits[y * self.words..(y + 1) * self.words]
+    }
+
+    fn set(&mut self, p: Point2) {
+        let (x, y) = (
+            (p.x - self.origin.x) as usize,
+            (p.y - self.origin.y) as usize,
+        );
+        self.bits[y * self.words + x / 64] |= 1 << (x % 64);
+    }
+
+    fn points(&self) -> impl Iterator<Item = Point2> + '_ {
+        self.bits.iter().enumerate().flat_map(move |(i, word)| {
+            let (y, w) = (i / self.words, i % self.words);
+            let mut word = *word;
+            std::iter::from_fn(move || {
+                (word != 0).then(|| {
+                    let b = word.trailing_zeros() as usize;
+                    word &= word - 1;
+                    self.origin
+                        + Point2 {
+                            x: (64 * w + b) as isize,
+                            y: y as isize,
+                        }
                 })
-                .collect_vec()
+            })
         })
-        .collect_vec();
+    }
+
+    /// Makes room around the elves if any of them are on the outermost rows or columns.
+    fn grow(&mut self) {
+        let last = self.height - 1;
+        let top = self.row(0).iter().any(|w| *w != 0);
+        let bottom = self.row(last).iter().any(|w| *w != 0);
+        let left = (0..self.height).any(|y| self.row(y)[0] & 1 != 0);
+        let right = (0..self.height).any(|y| self.row(y)[self.words - 1] >> 63 != 0);
+        if !(top || bottom || left || right) {
+            return;
+        }
+
+        let points = self.points().collect::<Vec<_>>();
+        let (top, bottom) = (usize::from(top), usize::from(bottom));
+        let (left, right) = (usize::from(left), usize::from(right));
+        self.origin = self.origin
+            - Point2 {
+                x: 64 * left as isize,
+                y: (Self::GROW_ROWS * top) as isize,
+            };
+        self.words += left + right;
+        self.height += Self::GROW_ROWS * (top + bottom);
+        self.bits = vec![0; self.words * self.height];
+        for p in points {
+            self.set(p);
+        }
+    }
+
+    /// Plays a round. Returns whether any elf moved.
+    fn round(&mut self) -> bool {
+        self.grow();
+        let (words, height) = (self.words, self.height);
+        let at = |y: usize, i: usize| y * words + i;
+
+        let order = self
+            .rules
+            .order(self.round)
+            .map(|dir| match dir {
+                Direction::North => NORTH,
+                Direction::South => SOUTH,
+                Direction::West => WEST,
+                Direction::East => EAST,
+                _ => panic!("elves only move in cardinal directions"),
+            })
+            .collect::<Vec<_>>();
+
+        // every elf with a neighbour proposes the first free direction, as one mask per direction
+        let mut proposals = [(); 4].map(|_| vec![0u64; words * height]);
+        for y in 1..height - 1 {
+            let (n, c, s) = (self.row(y - 1), self.row(y), self.row(y + 1));
+            for i in 0..words {
+                let (nw, nn, ne) = (west(n, i), n[i], east(n, i));
+                let (ww, ee) = (west(c, i), east(c, i));
+                let (sw, ss, se) = (west(s, i), s[i], east(s, i));
+                let mut free = [0; 4];
+                free[NORTH] = !(nw | nn | ne);
+                free[SOUTH] = !(sw | ss | se);
+                free[WEST] = !(nw | ww | sw);
+                free[EAST] = !(ne | ee | se);
+
+                let crowded = match self.rules.neighbourhood {
+                    Neighbourhood::None => !0,
+                    Neighbourhood::Cardinal => nn | ww | ee | ss,
+                    Neighbourhood::Ordinal => nw | ne | sw | se,
+                    Neighbourhood::All => nw | nn | ne | ww | ee | sw | ss | se,
+                };
+                let mut undecided = c[i] & crowded;
+                for &dir in &order {
+                    proposals[dir][at(y, i)] = undecided & free[dir];
+                    undecided &= !free[dir];
+                }
+            }
+        }
 
-    let grid = Grid::from_vec(v);
-    grid.map.keys().copied().collect::<HashSet<_>>()
+        // count the proposals for every point, saturating at two
+        let mut collisions = vec![0u64; words * height];
+        for y in 0..height {
+            for i in 0..words {
+                let row = |dir: usize| &proposals[dir][at(y, 0)..at(y + 1, 0)];
+                let incoming = [
+                    if y + 1 < height {
+                        proposals[NORTH][at(y + 1, i)]
+                    } else {
+                        0
+                    },
+                    if y > 0 {
+                        proposals[SOUTH][at(y - 1, i)]
+                    } else {
+                        0
+                    },
+                    east(row(WEST), i),
+                    west(row(EAST), i),
+                ];
+                let (mut once, mut twice) = (0, 0);
+                for m in incoming {
+                    twice |= once & m;
+                    once |= m;
+                }
+                collisions[at(y, i)] = twice;
+            }
+        }
+
+        // drop the proposals that collide
+        for y in 0..height {
+            let collided = &collisions[at(y, 0)..at(y + 1, 0)];
+            for i in 0..words {
+                if y > 0 {
+                    proposals[NORTH][at(y, i)] &= !collisions[at(y - 1, i)];
+                }
+                if y + 1 < height {
+                    proposals[SOUTH][at(y, i)] &= !collisions[at(y + 1, i)];
+                }
+                proposals[WEST][at(y, i)] &= !west(collided, i);
+                proposals[EAST][at(y, i)] &= !east(collided, i);
+            }
+        }
+
+        // move the rest
+        let mut moved = false;
+        for y in 0..height {
+            for i in 0..words {
+                let row = |dir: usize| &proposals[dir][at(y, 0)..at(y + 1, 0)];
+                let leaving = (0..4).fold(0, |acc, dir| acc | proposals[dir][at(y, i)]);
+                moved |= leaving != 0;
+                let mut arriving = east(row(WEST), i) | west(row(EAST), i);
+                if y + 1 < height {
+                    arriving |= proposals[NORTH][at(y + 1, i)];
+                }
+                if y > 0 {
+                    arriving |= proposals[SOUTH][at(y - 1, i)];
+                }
+                self.bits[at(y, i)] = (self.bits[at(y, i)] & !leaving) | arriving;
+            }
+        }
+
+        self.round += 1;
+        moved
+    }
+}
+
+pub(crate) fn parse(input: &str) -> Result<HashSet<Point2>, ParseError> {
+    let grid = Grid::parse(input, |c| match c {
+        '#' => Ok(Some(())),
+        '.' => Ok(None),
+        _ => Err("`#` or `.`"),
+    })
+    .map_err(|e| ParseError::from_grid(23, e))?;
+    if grid.map.is_empty() {
+        return Err(ParseError::new(23, 1, "at least one elf `#`"));
+    }
+    Ok(grid.map.keys().copied().collect::<HashSet<_>>())
 }
 
 #[cfg(test)]
 mod tests {
-    const INPUT: &str = "src/input/23.txt";
+    use std::collections::HashSet;
+
+    use super::{Backend, DiffusionRules, InvalidRules, Stop};
+    use crate::{
+        inputs::{example, real},
+        utils::{
+            automaton::Neighbourhood,
+            grid::{BoundingBox2, Direction, Point2},
+            render::Recorder,
+        },
+    };
 
     #[test]
     fn one() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(4000, super::one(&input));
+        let input = real(23);
+        assert_eq!(Ok(4000), super::one(&input));
     }
 
     #[test]
     fn two() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(1040, super::two(&input));
+        let input = real(23);
+        assert_eq!(Ok(1040), super::two(&input));
+    }
+
+    #[test]
+    fn one_example() {
+        let input = example(23, 1);
+        assert_eq!(Ok(110), super::one(&input));
+    }
+
+    #[test]
+    fn two_example() {
+        let input = example(23, 1);
+        assert_eq!(Ok(20), super::two(&input));
+    }
+
+    #[test]
+    fn backends_agree() {
+        let elves = super::parse(&example(23, 1)).unwrap();
+        for n in [0, 1, 2, 5, 10, 20] {
+            let rules = DiffusionRules {
+                stop: vec![Stop::Rounds(n), Stop::SteadyState],
+                ..DiffusionRules::part_one()
+            };
+            assert_backends_agree(&elves, &rules);
+        }
+    }
+
+    #[test]
+    fn bitboard_grows() {
+        // a line of elves wider than a word, spreading out in every direction
+        let elves = (0..100)
+            .flat_map(|x| [Point2 { x, y: 0 }, Point2 { x, y: 1 }])
+            .collect::<HashSet<_>>();
+        assert_backends_agree(&elves, &DiffusionRules::part_two());
+    }
+
+    #[test]
+    fn rule_variants() {
+        let elves = super::parse(&example(23, 1)).unwrap();
+        let variants = [
+            DiffusionRules {
+                rotate: false,
+                stop: vec![Stop::Rounds(30), Stop::SteadyState],
+                ..DiffusionRules::part_one()
+            },
+            DiffusionRules {
+                order: vec![Direction::East, Direction::South, Direction::West],
+                stop: vec![Stop::Rounds(50), Stop::SteadyState],
+                ..DiffusionRules::part_one()
+            },
+            DiffusionRules {
+                neighbourhood: Neighbourhood::Cardinal,
+                stop: vec![Stop::Rounds(50), Stop::SteadyState],
+                ..DiffusionRules::part_one()
+            },
+            DiffusionRules {
+                neighbourhood: Neighbourhood::None,
+                stop: vec![Stop::Rounds(15)],
+                ..DiffusionRules::part_one()
+            },
+        ];
+        for rules in &variants {
+            assert_backends_agree(&elves, rules);
+        }
+    }
+
+    #[test]
+    fn bounding_box_stop() {
+        let mut elves = super::parse(&example(23, 1)).unwrap();
+        let rules = DiffusionRules {
+            stop: vec![Stop::BoundingBox(100), Stop::SteadyState],
+            ..DiffusionRules::part_one()
+        };
+        let rounds =
+            super::rounds(&mut elves, &rules, Backend::Bitboard, &mut Recorder::off()).unwrap();
+        assert!(BoundingBox2::from_points(elves.iter().copied()).area() >= 100);
+        assert!(rounds < 20);
+    }
+
+    #[test]
+    fn parse_error() {
+        for input in ["", "...\n..."] {
+            let err = super::parse(input).unwrap_err();
+            assert_eq!(
+                "day 23, line 1: expected at least one elf `#`",
+                err.to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_rules() {
+        let endless = [
+            vec![],
+            vec![Stop::BoundingBox(100)],
+            vec![Stop::BoundingBox(100), Stop::SteadyState],
+        ];
+        for stop in endless {
+            let rules = DiffusionRules {
+                stop,
+                neighbourhood: Neighbourhood::None,
+                ..DiffusionRules::part_one()
+            };
+            assert_eq!(Err(InvalidRules::Endless), rules.validate());
+        }
+
+        // elves at (0, 0) and (1, 0) see each other and march north forever
+        let north = DiffusionRules {
+            order: vec![Direction::North],
+            rotate: false,
+            ..DiffusionRules::part_two()
+        };
+        assert_eq!(Err(InvalidRules::Endless), north.validate());
+        let capped = DiffusionRules {
+            stop: vec![Stop::Rounds(100), Stop::SteadyState],
+            ..north
+        };
+        let mut elves = super::parse("##").unwrap();
+        let rounds =
+            super::rounds(&mut elves, &capped, Backend::Bitboard, &mut Recorder::off()).unwrap();
+        assert_eq!(100, rounds);
+
+        let rules = DiffusionRules {
+            order: vec![Direction::North, Direction::SouthEast],
+            ..DiffusionRules::part_two()
+        };
+        assert_eq!(
+            Err(InvalidRules::NotCardinal(Direction::SouthEast)),
+            rules.validate()
+        );
+        let mut elves = super::parse(&example(23, 1)).unwrap();
+        let result = super::rounds(&mut elves, &rules, Backend::Bitboard, &mut Recorder::off());
+        assert!(result.is_err());
+    }
+
+    fn assert_backends_agree(elves: &HashSet<Point2>, rules: &DiffusionRules) {
+        let (mut automaton, mut bitboard) = (elves.clone(), elves.clone());
+        let mut off = Recorder::off();
+        let a = super::rounds(&mut automaton, rules, Backend::Automaton, &mut off).unwrap();
+        let b = super::rounds(&mut bitboard, rules, Backend::Bitboard, &mut off).unwrap();
+        assert_eq!((a, automaton), (b, bitboard), "with {:?}", rules);
     }
 }
@@ -1,176 +1,270 @@
-use std::fmt::Display;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
 
 use itertools::Itertools;
 
-use crate::utils::grid::{BoundingBox2, Direction, Grid, Point2};
+use crate::{
+    days::SolveError,
+    parse::ParseError,
+    utils::{
+        automaton::{Automaton, Boundary, Conflict, Neighbourhood, Neighbours, Rules},
+        grid::{BoundingBox2, DenseGrid, Direction, Grid, Point2},
+        render::{Recorder, Rgb, ToRgb},
+    },
+};
 
 #[allow(dead_code)]
-pub fn one(input: &str) -> u32 {
-    let grid = parse(input);
-    let start = Point2 { x: 1, y: 0 };
-    let end = Point2 {
-        x: grid.width as isize - 2,
-        y: grid.height as isize - 1,
-    };
-
-    let (node, _) = find_path(
-        &grid,
-        Node {
-            minute: 0,
-            point: start,
-        },
-        end,
-    );
-
-    node.minute as u32
+pub fn one(input: &str) -> Result<u32, SolveError> {
+    trips(input, 1, &mut Recorder::off()).map(|tour| tour.minutes as u32)
 }
 
 #[allow(dead_code)]
-pub fn two(input: &str) -> u32 {
-    let mut grid = parse(input);
-    let mut start = Point2 { x: 1, y: 0 };
-    let mut end = Point2 {
-        x: grid.width as isize - 2,
-        y: grid.height as isize - 1,
-    };
-
-    let mut nodes = Vec::new();
-    for _ in 0..3 {
-        let (node, end_grid) = find_path(
-            &grid,
-            Node {
-                minute: 0,
-                point: start,
-            },
-            end,
-        );
+pub fn two(input: &str) -> Result<u32, SolveError> {
+    trips(input, 3, &mut Recorder::off()).map(|tour| tour.minutes as u32)
+}
+
+/// Records the valley and the expedition every minute along the fastest route of `part`.
+pub(crate) fn record(input: &str, part: usize, recorder: &mut Recorder) -> Result<(), SolveError> {
+    trips(input, if part == 1 { 1 } else { 3 }, recorder).map(|_| ())
+}
 
-        grid = end_grid;
-        std::mem::swap(&mut start, &mut end);
+/// The fastest way to cross the valley `n` times, going back and forth.
+fn trips(input: &str, n: usize, recorder: &mut Recorder) -> Result<Tour, SolveError> {
+    let valley = Valley::new(&parse(input)?);
+    let (entrance, exit) = ([valley.entrance], [valley.exit]);
+    let waypoints = (0..=n)
+        .map(|i| if i % 2 == 0 { &entrance[..] } else { &exit[..] })
+        .collect_vec();
+    let tour = valley
+        .tour(&waypoints)
+        .ok_or_else(|| SolveError::unsolvable(24, "there is no way through the valley"))?;
 
-        nodes.push(node);
+    for (minute, point) in tour.path.iter().enumerate() {
+        recorder.record_with(|| {
+            let expedition = std::iter::once((*point, Rgb(255, 220, 0)));
+            valley.snapshot(minute).chain(expedition)
+        });
     }
 
-    nodes.into_iter().map(|n| n.minute).sum::<usize>() as u32
+    Ok(tour)
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-struct Node {
-    minute: usize,
-    point: Point2,
+/// The fastest route through a list of waypoints.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Tour {
+    pub minutes: usize,
+    /// The minute each waypoint after the first is reached.
+    pub arrivals: Vec<usize>,
+    /// Where the expedition is at every minute, from 0 to `minutes`.
+    pub path: Vec<Point2>,
 }
 
-fn find_path(grid: &Grid<Tile>, start: Node, end: Point2) -> (Node, Grid<Tile>) {
-    use pathfinding::prelude::*;
-
-    let mut grids = Vec::new();
-    grids.push(grid.clone());
+/// The valley at every minute of the blizzards' cycle. Blizzards wrap around the inner area, so
+/// their positions repeat every lcm(inner width, inner height) minutes.
+pub(crate) struct Valley {
+    /// The gap in the top wall.
+    pub entrance: Point2,
+    /// The gap in the bottom wall.
+    pub exit: Point2,
+    walls: DenseGrid<bool>,
+    /// The number of blizzards on each point, for every minute of the period.
+    blizzards: Vec<DenseGrid<u8>>,
+}
 
-    let bounding_box = BoundingBox2 {
-        lowest: Point2 { x: 0, y: 0 },
-        highest: Point2 {
-            x: grid.width as isize - 1,
-            y: grid.height as isize - 1,
-        },
-    };
+impl Valley {
+    pub fn new(grid: &Grid<Tile>) -> Self {
+        let (width, height) = (grid.width - 2, grid.height - 2);
+        let period = width / gcd(width, height) * height;
 
-    let path = bfs(
-        &start,
-        |n| {
-            let minute = n.minute + 1;
+        let mut walls = DenseGrid::new(grid.width, grid.height, false);
+        let mut blizzards = Vec::new();
+        for (p, tile) in &grid.map {
+            match tile {
+                Tile::Wall => walls[*p] = true,
+                Tile::Blizzard(dirs) => blizzards.extend(dirs.iter().map(|d| (*p, *d))),
+            }
+        }
 
-            // get the grid for the current `minute`, advancing it from the previous minute if
-            // necessary
-            let grid = match grids.get(minute) {
-                Some(g) => g,
-                None => {
-                    let prev_g = grids.get(minute - 1).unwrap();
-                    let g = advance_blizzards(&prev_g);
-                    grids.push(g);
-                    grids.get(minute).unwrap()
+        let mut automaton = Automaton::new(Blizzards::inside(grid), blizzards);
+        let blizzards = (0..period)
+            .map(|_| {
+                let mut counts = DenseGrid::new(grid.width, grid.height, 0);
+                for (p, blizzards) in &automaton.cells {
+                    counts[*p] = blizzards.len() as u8;
                 }
-            };
-
-            // evaluate successor states
-            [(0, 0), (1, 0), (0, 1), (-1, 0), (0, -1)]
-                .map(|(x, y)| n.point + Point2 { x, y })
-                .into_iter()
-                .filter(|p| {
-                    bounding_box.contains(p)
-                        && match grid.map.get(p) {
-                            Some(_) => false,
-                            None => true,
+                automaton.step();
+                counts
+            })
+            .collect();
+
+        Self {
+            entrance: gap(grid, 0).expect("checked when parsing"),
+            exit: gap(grid, grid.height - 1).expect("checked when parsing"),
+            walls,
+            blizzards,
+        }
+    }
+
+    /// The fastest way to visit every waypoint in order, starting at the first one at minute 0.
+    /// Each waypoint is a list of alternatives, any of which will do. `None` if there is no way
+    /// to visit them all.
+    pub fn tour(&self, waypoints: &[&[Point2]]) -> Option<Tour> {
+        let (first, rest) = waypoints.split_first()?;
+
+        // A state is a point and how many of the `rest` waypoints have been reached. The valley
+        // looks the same every `period` minutes, so a state seen before at the same point of the
+        // period can't lead anywhere faster. `layers` maps each state of a minute to the state
+        // it came from in the minute before.
+        let period = self.period();
+        let mut frontier = first
+            .iter()
+            .filter(|p| self.is_free(**p, 0))
+            .map(|p| (*p, 0))
+            .collect_vec();
+        let mut seen = frontier
+            .iter()
+            .map(|(p, leg)| (*p, 0, *leg))
+            .collect::<HashSet<_>>();
+        let mut layers = vec![frontier.iter().map(|s| (*s, *s)).collect::<HashMap<_, _>>()];
+
+        let mut minute = 0;
+        let last = loop {
+            if let Some(last) = frontier.iter().find(|(_, leg)| *leg == rest.len()) {
+                break *last;
+            }
+            if frontier.is_empty() {
+                return None;
+            }
+
+            minute += 1;
+            let mut next: HashMap<(Point2, usize), (Point2, usize)> = HashMap::new();
+            for &(point, leg) in &frontier {
+                for p in
+                    [(0, 0), (1, 0), (0, 1), (-1, 0), (0, -1)].map(|(x, y)| point + Point2 { x, y })
+                {
+                    if !self.is_free(p, minute) {
+                        continue;
+                    }
+                    let reached = leg + usize::from(rest[leg].contains(&p));
+                    // of several ways into a state, keep the one that had already reached the
+                    // most waypoints, so they are reached as early as possible
+                    match next.get_mut(&(p, reached)) {
+                        Some(previous) if leg > previous.1 => *previous = (point, leg),
+                        Some(_) => {}
+                        None if seen.insert((p, minute % period, reached)) => {
+                            next.insert((p, reached), (point, leg));
                         }
-                })
-                .map(|p| Node { point: p, minute })
-                .collect_vec()
-        },
-        |n| n.point == end,
-    )
-    .unwrap();
-
-    let node = path.last().unwrap();
-    (*node, grids[node.minute].clone())
-}
-
-fn advance_blizzards(grid: &Grid<Tile>) -> Grid<Tile> {
-    let mut target = grid.clone();
-    target.map.retain(|_, t| matches!(t, Tile::Wall));
-
-    for (point, blizzard) in grid
-        .map
-        .iter()
-        .filter_map(|(p, b)| match b {
-            Tile::Wall => None,
-            Tile::Blizzard(blizzards) => Some((p, blizzards)),
-        })
-        .flat_map(|(p, blizzards)| blizzards.into_iter().map(|b| (*p, *b)))
-    {
-        let target_point = blizzard.offset() + point;
-
-        // set target for blizzard
-        let target_point = match grid.map.get(&target_point) {
-            Some(Tile::Wall) => {
-                // wrap around
-                match blizzard {
-                    Direction::North => Point2 {
-                        y: grid.height as isize - 2,
-                        ..target_point
-                    },
-                    Direction::East => Point2 {
-                        x: 1,
-                        ..target_point
-                    },
-                    Direction::South => Point2 {
-                        y: 1,
-                        ..target_point
-                    },
-                    Direction::West => Point2 {
-                        x: grid.width as isize - 2,
-                        ..target_point
-                    },
-                    _ => panic!("invalid dir"),
+                        None => {}
+                    }
                 }
             }
-            _ => target_point,
+            frontier = next.keys().copied().collect();
+            layers.push(next);
         };
 
-        // move blizzard
-        target
-            .map
-            .entry(target_point)
-            .and_modify(|t| match t {
-                Tile::Blizzard(bs) => bs.push(blizzard),
-                _ => panic!("target map hit wall"),
-            })
-            .or_insert(Tile::Blizzard(vec![blizzard]));
+        // walk back through the layers to find the path
+        let mut path = vec![last.0];
+        let mut arrivals = Vec::new();
+        let mut state = last;
+        for minute in (1..=minute).rev() {
+            let previous = layers[minute][&state];
+            if previous.1 != state.1 {
+                arrivals.push(minute);
+            }
+            path.push(previous.0);
+            state = previous;
+        }
+        path.reverse();
+        arrivals.reverse();
+
+        Some(Tour {
+            minutes: minute,
+            arrivals,
+            path,
+        })
+    }
+
+    fn period(&self) -> usize {
+        self.blizzards.len()
+    }
+
+    fn is_free(&self, p: Point2, minute: usize) -> bool {
+        self.walls.get(p) == Some(&false) && self.blizzards[minute % self.period()][p] == 0
+    }
+
+    fn snapshot(&self, minute: usize) -> impl Iterator<Item = (Point2, Rgb)> + '_ {
+        let blizzards = &self.blizzards[minute % self.period()];
+        self.walls.points().filter_map(move |p| {
+            if self.walls[p] {
+                Some((p, Tile::Wall.to_rgb()))
+            } else {
+                Some(blizzards[p] as usize)
+                    .filter(|n| *n > 0)
+                    .map(|n| (p, blizzard_rgb(n)))
+            }
+        })
     }
+}
+
+/// The first point in row `y` that isn't a wall.
+fn gap(grid: &Grid<Tile>, y: usize) -> Option<Point2> {
+    (0..grid.width as isize)
+        .map(|x| Point2 { x, y: y as isize })
+        .find(|p| !grid.map.contains_key(p))
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
 
-    target
+/// Blizzards keep moving in their direction, pass through each other, and come back in on the
+/// opposite side of the valley when they reach a wall.
+struct Blizzards {
+    inner: BoundingBox2,
+}
+
+impl Blizzards {
+    fn inside(grid: &Grid<Tile>) -> Self {
+        Self {
+            inner: BoundingBox2 {
+                lowest: Point2 { x: 1, y: 1 },
+                highest: Point2 {
+                    x: grid.width as isize - 2,
+                    y: grid.height as isize - 2,
+                },
+            },
+        }
+    }
+}
+
+impl Rules for Blizzards {
+    type Entity = Direction;
+
+    fn neighbourhood(&self) -> Neighbourhood {
+        Neighbourhood::None
+    }
+
+    fn boundary(&self) -> Boundary {
+        Boundary::Wrapping(self.inner)
+    }
+
+    fn conflict(&self) -> Conflict {
+        Conflict::Stack
+    }
+
+    fn propose(&self, _: Point2, blizzard: &Direction, _: Neighbours) -> Option<Direction> {
+        Some(*blizzard)
+    }
 }
 
 #[derive(Clone, Debug)]
-enum Tile {
+pub(crate) enum Tile {
     Wall,
     Blizzard(Vec<Direction>),
 }
@@ -195,39 +289,111 @@ impl Display for Tile {
     }
 }
 
-fn parse(input: &str) -> Grid<Tile> {
-    Grid::from_vec(
-        input
-            .lines()
-            .map(|l| {
-                l.chars()
-                    .map(|c| match c {
-                        '#' => Some(Tile::Wall),
-                        '<' => Some(Tile::Blizzard(vec![Direction::West])),
-                        'v' => Some(Tile::Blizzard(vec![Direction::South])),
-                        '>' => Some(Tile::Blizzard(vec![Direction::East])),
-                        '^' => Some(Tile::Blizzard(vec![Direction::North])),
-                        _ => None,
-                    })
-                    .collect()
-            })
-            .collect(),
-    )
+impl ToRgb for Tile {
+    fn to_rgb(&self) -> Rgb {
+        match self {
+            Tile::Wall => Rgb(90, 90, 90),
+            Tile::Blizzard(dirs) => blizzard_rgb(dirs.len()),
+        }
+    }
+}
+
+/// The more blizzards on a point, the deeper the blue.
+fn blizzard_rgb(count: usize) -> Rgb {
+    let v = (255 - 40 * count.min(4)) as u8;
+    Rgb(v, v, 255)
+}
+
+pub(crate) fn parse(input: &str) -> Result<Grid<Tile>, ParseError> {
+    let grid = Grid::parse(input, |c| match c {
+        '#' => Ok(Some(Tile::Wall)),
+        '<' => Ok(Some(Tile::Blizzard(vec![Direction::West]))),
+        'v' => Ok(Some(Tile::Blizzard(vec![Direction::South]))),
+        '>' => Ok(Some(Tile::Blizzard(vec![Direction::East]))),
+        '^' => Ok(Some(Tile::Blizzard(vec![Direction::North]))),
+        '.' => Ok(None),
+        _ => Err("one of `#`, `.`, `<`, `>`, `^` or `v`"),
+    })
+    .map_err(|e| ParseError::from_grid(24, e))?;
+    if grid.width < 3 || grid.height < 3 {
+        return Err(ParseError::new(24, 1, "a valley with walls around it"));
+    }
+    if gap(&grid, 0).is_none() {
+        return Err(ParseError::new(24, 1, "a gap in the top wall"));
+    }
+    if gap(&grid, grid.height - 1).is_none() {
+        return Err(ParseError::new(24, grid.height, "a gap in the bottom wall"));
+    }
+    Ok(grid)
 }
 
 #[cfg(test)]
 mod tests {
-    const INPUT: &str = "src/input/24.txt";
+    use crate::inputs::{example, real};
 
     #[test]
     fn one() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(322, super::one(&input));
+        let input = real(24);
+        assert_eq!(Ok(322), super::one(&input));
     }
 
     #[test]
     fn two() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!(974, super::two(&input));
+        let input = real(24);
+        assert_eq!(Ok(974), super::two(&input));
+    }
+
+    #[test]
+    fn one_example() {
+        let input = example(24, 1);
+        assert_eq!(Ok(18), super::one(&input));
+    }
+
+    #[test]
+    fn two_example() {
+        let input = example(24, 1);
+        assert_eq!(Ok(54), super::two(&input));
+    }
+
+    #[test]
+    fn tour() {
+        let valley = super::Valley::new(&super::parse(&example(24, 1)).unwrap());
+        let (entrance, exit) = ([valley.entrance], [valley.exit]);
+        let tour = valley.tour(&[&entrance, &exit, &entrance, &exit]).unwrap();
+        assert_eq!(54, tour.minutes);
+        assert_eq!(vec![18, 41, 54], tour.arrivals);
+        assert_eq!(55, tour.path.len());
+        assert_eq!((entrance[0], exit[0]), (tour.path[0], tour.path[54]));
+
+        // a second way out, right below the entrance
+        let inside = super::Point2 { x: 1, y: 1 };
+        let tour = valley.tour(&[&entrance, &[exit[0], inside]]).unwrap();
+        assert_eq!(vec![1], tour.arrivals);
+
+        let wall = super::Point2 { x: 0, y: 0 };
+        assert_eq!(None, valley.tour(&[&entrance, &[wall]]));
+    }
+
+    #[test]
+    fn no_way_through() {
+        let err = super::one("#.#\n#>#\n#.#").unwrap_err();
+        assert_eq!(
+            "day 24: there is no way through the valley",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn period() {
+        let valley = super::Valley::new(&super::parse(&example(24, 1)).unwrap());
+        assert_eq!(12, valley.period());
+
+        let input = "#.#####\n#.....#\n#>....#\n#.....#\n#...v.#\n#.....#\n#####.#";
+        let valley = super::Valley::new(&super::parse(input).unwrap());
+        assert_eq!(5, valley.period());
+        // the `>` blizzard is back where it started after 5 minutes
+        assert!(!valley.is_free(super::Point2 { x: 1, y: 2 }, 0));
+        assert!(valley.is_free(super::Point2 { x: 1, y: 2 }, 1));
+        assert!(!valley.is_free(super::Point2 { x: 1, y: 2 }, 5));
     }
 }
@@ -1,13 +1,15 @@
 use self::snafu::Snafu;
+use crate::parse::{self, ParseError};
 
 #[allow(dead_code)]
-pub fn one(input: &str) -> String {
-    let sum = input
-        .lines()
-        .map(|l| l.parse::<Snafu>().unwrap())
-        .sum::<Snafu>();
-
-    format!("{}", sum)
+pub fn one(input: &str) -> Result<String, ParseError> {
+    let sum = parse::lines(25, input, "a SNAFU number like `1=-0-2`", |l| {
+        l.parse::<Snafu>().ok()
+    })?
+    .into_iter()
+    .sum::<Snafu>();
+
+    Ok(format!("{}", sum))
 }
 
 mod snafu {
@@ -18,7 +20,7 @@ mod snafu {
     #[derive(Clone, PartialEq, Eq, Debug)]
     pub struct Snafu(Vec<i8>);
 
-    impl<'a, 'b> Add<&'_ Snafu> for &'_ Snafu {
+    impl Add<&'_ Snafu> for &'_ Snafu {
         type Output = Snafu;
 
         fn add(self, rhs: &'_ Snafu) -> Snafu {
@@ -161,11 +163,17 @@ mod snafu {
 
 #[cfg(test)]
 mod tests {
-    const INPUT: &str = "src/input/25.txt";
+    use crate::inputs::{example, real};
 
     #[test]
     fn one() {
-        let input = std::fs::read_to_string(INPUT).unwrap();
-        assert_eq!("2=20---01==222=0=0-2", super::one(&input));
+        let input = real(25);
+        assert_eq!(Ok("2=20---01==222=0=0-2"), super::one(&input).as_deref());
+    }
+
+    #[test]
+    fn one_example() {
+        let input = example(25, 1);
+        assert_eq!(Ok("2=-1=0"), super::one(&input).as_deref());
     }
 }
@@ -0,0 +1,146 @@
+use std::{
+    fmt::Display,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+/// Environment variable overriding the directory inputs are read from.
+pub const INPUT_DIR_VAR: &str = "AOC_INPUT_DIR";
+
+/// Which of a day's inputs to use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Input {
+    /// The personal puzzle input, `NN.txt`.
+    Real,
+    /// One of the examples from the puzzle text, `NN.exampleN.txt`, numbered from 1.
+    Example(usize),
+}
+
+/// Resolves and reads puzzle inputs from a single directory.
+#[derive(Clone, Debug)]
+pub struct Inputs {
+    root: PathBuf,
+}
+
+#[derive(Debug)]
+pub struct InputError {
+    pub day: u8,
+    pub input: Input,
+    pub path: PathBuf,
+    pub kind: InputErrorKind,
+}
+
+#[derive(Debug)]
+pub enum InputErrorKind {
+    Missing,
+    NotUtf8,
+    Io(std::io::Error),
+}
+
+impl Inputs {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Inputs { root: root.into() }
+    }
+
+    /// Uses the directory in `AOC_INPUT_DIR`, falling back to `src/input` in the crate.
+    pub fn from_env() -> Self {
+        match std::env::var_os(INPUT_DIR_VAR) {
+            Some(dir) => Inputs::new(dir),
+            None => Inputs::new(Path::new(env!("CARGO_MANIFEST_DIR")).join("src/input")),
+        }
+    }
+
+    pub fn path(&self, day: u8, input: Input) -> PathBuf {
+        let name = match input {
+            Input::Real => format!("{:02}.txt", day),
+            Input::Example(n) => format!("{:02}.example{}.txt", day, n),
+        };
+        self.root.join(name)
+    }
+
+    pub fn read(&self, day: u8, input: Input) -> Result<String, InputError> {
+        let path = self.path(day, input);
+        std::fs::read_to_string(&path).map_err(|e| InputError {
+            day,
+            input,
+            kind: match e.kind() {
+                ErrorKind::NotFound => InputErrorKind::Missing,
+                ErrorKind::InvalidData => InputErrorKind::NotUtf8,
+                _ => InputErrorKind::Io(e),
+            },
+            path,
+        })
+    }
+}
+
+impl Display for Input {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Input::Real => write!(f, "input"),
+            Input::Example(n) => write!(f, "example {}", n),
+        }
+    }
+}
+
+impl Display for InputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (day, input, path) = (self.day, self.input, self.path.display());
+        match &self.kind {
+            InputErrorKind::Missing => write!(f, "day {} {} not found at {}", day, input, path),
+            InputErrorKind::NotUtf8 => write!(
+                f,
+                "day {} {} at {} is not valid UTF-8 (is it still encrypted?)",
+                day, input, path
+            ),
+            InputErrorKind::Io(e) => {
+                write!(f, "failed to read day {} {} at {}: {}", day, input, path, e)
+            }
+        }
+    }
+}
+
+impl std::error::Error for InputError {}
+
+/// Reads the real input for `day`, panicking with a readable message if it isn't available.
+#[cfg(test)]
+pub fn real(day: u8) -> String {
+    Inputs::from_env()
+        .read(day, Input::Real)
+        .unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Reads example `n` for `day`, panicking with a readable message if it isn't available.
+#[cfg(test)]
+pub fn example(day: u8, n: usize) -> String {
+    Inputs::from_env()
+        .read(day, Input::Example(n))
+        .unwrap_or_else(|e| panic!("{}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{Input, InputErrorKind, Inputs};
+
+    #[test]
+    fn paths() {
+        let inputs = Inputs::new("inputs");
+        assert_eq!(Path::new("inputs/07.txt"), inputs.path(7, Input::Real));
+        assert_eq!(
+            Path::new("inputs/12.example2.txt"),
+            inputs.path(12, Input::Example(2))
+        );
+    }
+
+    #[test]
+    fn missing() {
+        let err = Inputs::new("does-not-exist")
+            .read(3, Input::Example(1))
+            .unwrap_err();
+        assert!(matches!(err.kind, InputErrorKind::Missing));
+        assert!(err
+            .to_string()
+            .starts_with("day 3 example 1 not found at does-not-exist"));
+    }
+}
@@ -1,18 +1,81 @@
-use std::env;
+use std::{env, process::ExitCode};
 
+use answer::Answer;
+use inputs::{Input, Inputs};
+
+mod animate;
+mod answer;
+mod bench;
 mod days;
+mod inputs;
+mod ocr;
+mod parse;
+mod scaffold;
 mod utils;
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    let input = std::fs::read_to_string("src/input/example").unwrap();
-    let result = match args.into_iter().skip(1).next().map(|n| n.parse::<usize>()) {
-        Some(Ok(1)) => days::day25::one(&input),
-        _ => {
-            eprintln!("Must pass either `1` or `2` as first argument.");
-            return;
+const USAGE: &str = "usage: aoc-2022 <day> <part> [--input PATH | --example [N]]";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("animate") => animate::command(&args[1..]),
+        Some("bench") => bench::command(&args[1..]),
+        Some("scaffold") => scaffold::command(&args[1..]),
+        _ => solve(&args).map(|answer| println!("{}", answer)),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
         }
+    }
+}
+
+fn solve(args: &[String]) -> Result<Answer, String> {
+    let usage = || {
+        format!(
+            "{}\n{}\n{}\n{}",
+            USAGE,
+            animate::USAGE,
+            bench::USAGE,
+            scaffold::USAGE
+        )
     };
+    let (day, part, rest) = match args {
+        [day, part, rest @ ..] => (day, part, rest),
+        _ => return Err(usage()),
+    };
+
+    let day = day
+        .parse::<u8>()
+        .map_err(|_| format!("invalid day `{}`\n{}", day, usage()))?;
+    let part = part
+        .parse::<usize>()
+        .map_err(|_| format!("invalid part `{}`\n{}", part, usage()))?;
+
+    let solution = days::get(day).ok_or_else(|| format!("day {} is not implemented", day))?;
+    let solve = solution
+        .part(part)
+        .ok_or_else(|| format!("day {} has no part {}", day, part))?;
+
+    let inputs = Inputs::from_env();
+    let input = match rest {
+        [] => inputs.read(day, Input::Real),
+        [flag] if flag == "--example" => inputs.read(day, Input::Example(1)),
+        [flag, n] if flag == "--example" => {
+            let n = n.parse().map_err(|_| usage())?;
+            inputs.read(day, Input::Example(n))
+        }
+        [flag, path] if flag == "--input" => {
+            return std::fs::read_to_string(path)
+                .map_err(|e| format!("failed to read input `{}`: {}", path, e))
+                .and_then(|input| solve(&input).map_err(|e| e.to_string()));
+        }
+        _ => return Err(usage()),
+    }
+    .map_err(|e| e.to_string())?;
 
-    eprintln!("{}", result);
+    solve(&input).map_err(|e| e.to_string())
 }
@@ -0,0 +1,235 @@
+use std::fmt::Display;
+
+use crate::utils::grid::{BoundingBox2, Grid};
+
+/// Letters of the font most pixel art puzzles use: 6 pixels high, and mostly 4 wide.
+#[rustfmt::skip]
+const SMALL: &[(char, [&str; 6])] = &[
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', ["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+/// Letters of the larger font, 10 pixels high and 6 wide.
+#[rustfmt::skip]
+const LARGE: &[(char, [&str; 10])] = &[
+    ('A', ["..##..", ".#..#.", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#", "#....#"]),
+    ('B', ["#####.", "#....#", "#....#", "#....#", "#####.", "#....#", "#....#", "#....#", "#....#", "#####."]),
+    ('C', [".####.", "#....#", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#....#", ".####."]),
+    ('E', ["######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....", "#.....", "######"]),
+    ('F', ["######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....", "#.....", "#....."]),
+    ('G', [".####.", "#....#", "#.....", "#.....", "#.....", "#..###", "#....#", "#....#", "#...##", ".###.#"]),
+    ('H', ["#....#", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#", "#....#", "#....#"]),
+    ('J', ["...###", "....#.", "....#.", "....#.", "....#.", "....#.", "....#.", "#...#.", "#...#.", ".###.."]),
+    ('K', ["#....#", "#...#.", "#..#..", "#.#...", "##....", "##....", "#.#...", "#..#..", "#...#.", "#....#"]),
+    ('L', ["#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "######"]),
+    ('N', ["#....#", "##...#", "##...#", "#.#..#", "#.#..#", "#..#.#", "#..#.#", "#...##", "#...##", "#....#"]),
+    ('P', ["#####.", "#....#", "#....#", "#....#", "#####.", "#.....", "#.....", "#.....", "#.....", "#....."]),
+    ('R', ["#####.", "#....#", "#....#", "#....#", "#####.", "#..#..", "#...#.", "#...#.", "#....#", "#....#"]),
+    ('X', ["#....#", "#....#", ".#..#.", ".#..#.", "..##..", "..##..", ".#..#.", ".#..#.", "#....#", "#....#"]),
+    ('Z', ["######", ".....#", ".....#", "....#.", "...#..", "..#...", ".#....", "#.....", "#.....", "######"]),
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OcrError {
+    /// Neither font is this many pixels high.
+    UnknownFont { height: usize },
+    /// The glyph starting at `column` isn't a letter of the font. `glyph` is its pixels, one
+    /// line per row.
+    UnknownGlyph { column: usize, glyph: String },
+}
+
+impl Display for OcrError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OcrError::UnknownFont { height } => {
+                write!(f, "no font is {} pixels high", height)
+            }
+            OcrError::UnknownGlyph { column, glyph } => {
+                write!(f, "unknown glyph at column {}:\n{}", column, glyph)
+            }
+        }
+    }
+}
+
+impl std::error::Error for OcrError {}
+
+/// Reads the letters in rows of pixels, where `#` is lit and anything else isn't. Letters are
+/// separated by at least one column without lit pixels, and the font is picked by the height of
+/// the lit pixels.
+pub fn read<S: AsRef<str>>(rows: &[S]) -> Result<String, OcrError> {
+    let bitmap = rows
+        .iter()
+        .map(|row| row.as_ref().chars().map(|c| c == '#').collect())
+        .collect::<Vec<Vec<bool>>>();
+    read_bitmap(&bitmap)
+}
+
+/// Like [`read`], for rows separated by newlines.
+#[allow(dead_code)]
+pub fn read_str(art: &str) -> Result<String, OcrError> {
+    read(&art.lines().collect::<Vec<_>>())
+}
+
+/// Like [`read`], where the points of `grid` that are `true` are lit.
+#[allow(dead_code)]
+pub fn read_grid(grid: &Grid<bool>) -> Result<String, OcrError> {
+    let lit = grid.map.iter().filter(|(_, v)| **v).map(|(p, _)| *p);
+    let bb = BoundingBox2::from_points(lit.clone());
+    if bb.lowest.x > bb.highest.x {
+        return Ok(String::new());
+    }
+    let (width, height) = (
+        bb.lowest.x.abs_diff(bb.highest.x) + 1,
+        bb.lowest.y.abs_diff(bb.highest.y) + 1,
+    );
+    let mut bitmap = vec![vec![false; width]; height];
+    for p in lit {
+        let p = p - bb.lowest;
+        bitmap[p.y as usize][p.x as usize] = true;
+    }
+    read_bitmap(&bitmap)
+}
+
+fn read_bitmap(bitmap: &[Vec<bool>]) -> Result<String, OcrError> {
+    let lit_rows = bitmap
+        .iter()
+        .enumerate()
+        .filter(|(_, row)| row.contains(&true))
+        .map(|(y, _)| y)
+        .collect::<Vec<_>>();
+    let (Some(&top), Some(&bottom)) = (lit_rows.first(), lit_rows.last()) else {
+        return Ok(String::new());
+    };
+    let rows = &bitmap[top..=bottom];
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let lit = |x: usize, y: usize| rows[y].get(x).copied().unwrap_or(false);
+    let column_lit = |x: usize| (0..rows.len()).any(|y| lit(x, y));
+
+    let mut text = String::new();
+    let mut x = 0;
+    while x < width {
+        if !column_lit(x) {
+            x += 1;
+            continue;
+        }
+        let start = x;
+        while x < width && column_lit(x) {
+            x += 1;
+        }
+
+        let glyph = (0..rows.len())
+            .map(|y| {
+                (start..x)
+                    .map(|x| if lit(x, y) { '#' } else { '.' })
+                    .collect()
+            })
+            .collect::<Vec<String>>();
+        let letter = match rows.len() {
+            6 => find(SMALL, &glyph),
+            10 => find(LARGE, &glyph),
+            height => return Err(OcrError::UnknownFont { height }),
+        };
+        text.push(letter.ok_or_else(|| OcrError::UnknownGlyph {
+            column: start,
+            glyph: glyph.join("\n"),
+        })?);
+    }
+    Ok(text)
+}
+
+/// The letter of `font` drawn by `glyph`, ignoring the font's empty columns at the sides.
+fn find<const H: usize>(font: &[(char, [&str; H])], glyph: &[String]) -> Option<char> {
+    font.iter().find_map(|(letter, rows)| {
+        let width = rows[0].len();
+        let lit = |x: usize| rows.iter().any(|row| row.as_bytes()[x] == b'#');
+        let first = (0..width).find(|x| lit(*x))?;
+        let last = (0..width).rev().find(|x| lit(*x))?;
+        rows.iter()
+            .zip(glyph)
+            .all(|(row, g)| row[first..=last] == *g)
+            .then_some(*letter)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OcrError;
+    use crate::utils::grid::Grid;
+
+    const PBZGRAZA: [&str; 6] = [
+        "###..###..####..##..###...##..####..##..",
+        "#..#.#..#....#.#..#.#..#.#..#....#.#..#.",
+        "#..#.###....#..#....#..#.#..#...#..#..#.",
+        "###..#..#..#...#.##.###..####..#...####.",
+        "#....#..#.#....#..#.#.#..#..#.#....#..#.",
+        "#....###..####..###.#..#.#..#.####.#..#.",
+    ];
+
+    #[test]
+    fn small_font() {
+        assert_eq!(Ok("PBZGRAZA".to_string()), super::read(&PBZGRAZA));
+
+        let art = "\n.###.#...#\n..#..#...#\n..#...#.#.\n..#....#..\n..#....#..\n.###...#..\n";
+        assert_eq!(Ok("IY".to_string()), super::read_str(art));
+    }
+
+    #[test]
+    fn large_font() {
+        let art = [
+            "#....#..######",
+            "#....#.......#",
+            ".#..#........#",
+            ".#..#.......#.",
+            "..##.......#..",
+            "..##......#...",
+            ".#..#....#....",
+            ".#..#...#.....",
+            "#....#..#.....",
+            "#....#..######",
+        ];
+        assert_eq!(Ok("XZ".to_string()), super::read(&art));
+    }
+
+    #[test]
+    fn grid() {
+        let rows = PBZGRAZA
+            .iter()
+            .map(|row| row.chars().map(|c| Some(c == '#')).collect())
+            .collect();
+        let grid = Grid::from_vec(rows);
+        assert_eq!(Ok("PBZGRAZA".to_string()), super::read_grid(&grid));
+    }
+
+    #[test]
+    fn errors() {
+        let art = ["##..#", "##..#", "....#", "....#", "....#", "....#"];
+        let err = super::read(&art).unwrap_err();
+        assert_eq!(
+            OcrError::UnknownGlyph {
+                column: 0,
+                glyph: "##\n##\n..\n..\n..\n..".to_string(),
+            },
+            err
+        );
+
+        let err = super::read(&["#", "#"]).unwrap_err();
+        assert_eq!(OcrError::UnknownFont { height: 2 }, err);
+        assert_eq!("no font is 2 pixels high", err.to_string());
+    }
+}
@@ -0,0 +1,105 @@
+use std::fmt::Display;
+
+use crate::utils::grid::GridParseError;
+
+/// A puzzle input that doesn't look the way the puzzle says it should. Lines and columns start at
+/// 1.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub day: u8,
+    pub line: usize,
+    pub column: Option<usize>,
+    pub expected: String,
+}
+
+impl ParseError {
+    pub fn new(day: u8, line: usize, expected: impl Into<String>) -> Self {
+        ParseError {
+            day,
+            line,
+            column: None,
+            expected: expected.into(),
+        }
+    }
+
+    pub fn at_column(mut self, column: usize) -> Self {
+        self.column = Some(column);
+        self
+    }
+
+    pub fn from_grid<E: Display>(day: u8, err: GridParseError<E>) -> Self {
+        ParseError::new(day, err.line, err.error.to_string()).at_column(err.column)
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "day {}, line {}", self.day, self.line)?;
+        if let Some(column) = self.column {
+            write!(f, ", column {}", column)?;
+        }
+        write!(f, ": expected {}", self.expected)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses every line of `input` with `parse`, failing on the first line it returns `None` for.
+pub fn lines<'a, T, F>(
+    day: u8,
+    input: &'a str,
+    expected: &str,
+    parse: F,
+) -> Result<Vec<T>, ParseError>
+where
+    F: FnMut(&'a str) -> Option<T>,
+{
+    lines_from(day, input, 1, expected, parse)
+}
+
+/// Like [`lines`], for a part of the input that starts at line `first_line`.
+pub fn lines_from<'a, T, F>(
+    day: u8,
+    input: &'a str,
+    first_line: usize,
+    expected: &str,
+    mut parse: F,
+) -> Result<Vec<T>, ParseError>
+where
+    F: FnMut(&'a str) -> Option<T>,
+{
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, l)| parse(l).ok_or_else(|| ParseError::new(day, first_line + i, expected)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ParseError;
+
+    #[test]
+    fn display() {
+        let err = ParseError::new(11, 14, "`Test: divisible by`");
+        assert_eq!(
+            "day 11, line 14: expected `Test: divisible by`",
+            err.to_string()
+        );
+
+        let err = ParseError::new(22, 3, "`#` or `.`").at_column(7);
+        assert_eq!(
+            "day 22, line 3, column 7: expected `#` or `.`",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn lines() {
+        let parsed = super::lines(1, "1\n2\n", "a number", |l| l.parse::<u32>().ok());
+        assert_eq!(Ok(vec![1, 2]), parsed);
+
+        let parsed = super::lines(1, "1\nx\n3", "a number", |l| l.parse::<u32>().ok());
+        assert_eq!(Err(ParseError::new(1, 2, "a number")), parsed);
+    }
+}
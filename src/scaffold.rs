@@ -0,0 +1,227 @@
+use std::{fs, path::Path};
+
+pub const USAGE: &str = "usage: aoc-2022 scaffold <day>";
+
+/// Entry point for the `scaffold` subcommand.
+pub fn command(args: &[String]) -> Result<(), String> {
+    let day = match args {
+        [day] => day.parse::<u8>().ok().filter(|d| (1..=25).contains(d)),
+        _ => None,
+    }
+    .ok_or_else(|| USAGE.to_string())?;
+
+    for step in scaffold(Path::new("."), day)? {
+        println!("{}", step);
+    }
+
+    Ok(())
+}
+
+/// Sets up everything needed to start working on `day` inside the repository at `root`.
+///
+/// Every step is skipped if it was already done, so this can safely be run more than once. An
+/// existing day module is never overwritten. Returns a description of each step.
+pub fn scaffold(root: &Path, day: u8) -> Result<Vec<String>, String> {
+    let module = format!("day{:02}", day);
+    let input = format!("src/input/{:02}.txt", day);
+    let mut steps = Vec::new();
+
+    // day module
+    let module_path = root.join("src/days").join(format!("{}.rs", module));
+    if module_path.exists() {
+        steps.push(format!(
+            "{} already exists, leaving it untouched",
+            display(&module_path)
+        ));
+    } else {
+        let skeleton = read(&root.join("skeleton.rs"))?;
+        write(
+            &module_path,
+            &skeleton.replace("REPLACEME", &day.to_string()),
+        )?;
+        steps.push(format!("created {}", display(&module_path)));
+    }
+
+    // registration
+    let days_path = root.join("src/days.rs");
+    let days = read(&days_path)?;
+    let (days, registered) = register(&days, day)?;
+    if registered {
+        write(&days_path, &days)?;
+        steps.push(format!("registered {} in {}", module, display(&days_path)));
+    } else {
+        steps.push(format!("{} is already registered", module));
+    }
+
+    // placeholder input
+    let input_path = root.join(&input);
+    if input_path.exists() {
+        steps.push(format!("{} already exists", display(&input_path)));
+    } else {
+        write(&input_path, "")?;
+        steps.push(format!("created empty {}", display(&input_path)));
+    }
+
+    Ok(steps)
+}
+
+/// Adds the `mod` declaration and the `SOLUTIONS` entry for `day` to the contents of `days.rs`,
+/// keeping both sorted by day. Returns whether anything had to be added.
+fn register(days: &str, day: u8) -> Result<(String, bool), String> {
+    let mut lines: Vec<String> = days.lines().map(str::to_owned).collect();
+    let mut changed = false;
+
+    // mod declaration
+    let decl = format!("pub mod day{:02};", day);
+    if !lines.contains(&decl) {
+        let mods = lines
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| l.starts_with("pub mod day"))
+            .map(|(i, l)| (i, l.as_str()))
+            .collect::<Vec<_>>();
+        let idx = mods
+            .iter()
+            .find(|(_, l)| *l > decl.as_str())
+            .map(|(i, _)| *i)
+            .or_else(|| mods.last().map(|(i, _)| i + 1))
+            .unwrap_or(0);
+        lines.insert(idx, decl);
+        changed = true;
+    }
+
+    // registry entry
+    let start = lines
+        .iter()
+        .position(|l| l.starts_with("pub const SOLUTIONS"))
+        .ok_or("could not find `SOLUTIONS` in days.rs")?;
+    let end = start
+        + lines[start..]
+            .iter()
+            .position(|l| l == "];")
+            .ok_or("could not find the end of `SOLUTIONS` in days.rs")?;
+
+    // each entry starts with a line at the array's indentation and is either a `solution!`
+    // invocation or a `Solution` literal with its day on one of the following lines
+    let entries = (start + 1..end)
+        .filter(|i| lines[*i].starts_with("    ") && !lines[*i].starts_with("     "))
+        .filter_map(|i| entry_day(&lines[i..end]).map(|d| (i, d)))
+        .collect::<Vec<_>>();
+
+    if !entries.iter().any(|(_, d)| *d == day) {
+        let idx = entries
+            .iter()
+            .find(|(_, d)| *d > day)
+            .map(|(i, _)| *i)
+            .unwrap_or(end);
+        lines.insert(idx, format!("    solution!({}, day{:02}),", day, day));
+        changed = true;
+    }
+
+    let mut days = lines.join("\n");
+    days.push('\n');
+    Ok((days, changed))
+}
+
+fn entry_day(lines: &[String]) -> Option<u8> {
+    let first = lines[0].trim();
+    if let Some(args) = first.strip_prefix("solution!(") {
+        args.split(',').next()?.trim().parse().ok()
+    } else if first.starts_with("Solution {") {
+        lines[1..]
+            .iter()
+            .find_map(|l| l.trim().strip_prefix("day: "))?
+            .trim_end_matches(',')
+            .parse()
+            .ok()
+    } else {
+        None
+    }
+}
+
+fn display(path: &Path) -> String {
+    path.strip_prefix(".").unwrap_or(path).display().to_string()
+}
+
+fn read(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", display(path), e))
+}
+
+fn write(path: &Path, contents: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("failed to create {}: {}", display(parent), e))?;
+    }
+    fs::write(path, contents).map_err(|e| format!("failed to write {}: {}", display(path), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    const DAYS: &str = "pub mod day01;
+pub mod day03;
+
+pub const SOLUTIONS: &[Solution] = &[
+    solution!(1, day01),
+    Solution {
+        day: 3,
+        parse: None,
+        parts: &[],
+    },
+];
+";
+
+    #[test]
+    fn register() {
+        let (days, changed) = super::register(DAYS, 2).unwrap();
+        assert!(changed);
+        assert_eq!(
+            "pub mod day01;
+pub mod day02;
+pub mod day03;
+
+pub const SOLUTIONS: &[Solution] = &[
+    solution!(1, day01),
+    solution!(2, day02),
+    Solution {
+        day: 3,
+        parse: None,
+        parts: &[],
+    },
+];
+",
+            days
+        );
+
+        let (days, changed) = super::register(&days, 4).unwrap();
+        assert!(changed);
+        assert!(days.contains("pub mod day03;\npub mod day04;\n"));
+        assert!(days.contains("    },\n    solution!(4, day04),\n];"));
+
+        assert_eq!((days.clone(), false), super::register(&days, 4).unwrap());
+    }
+
+    #[test]
+    fn scaffold() {
+        let root = std::env::temp_dir().join(format!("aoc-2022-scaffold-{}", std::process::id()));
+        fs::create_dir_all(root.join("src/days")).unwrap();
+        fs::write(root.join("skeleton.rs"), "let input = real(REPLACEME);\n").unwrap();
+        fs::write(root.join("src/days.rs"), DAYS).unwrap();
+
+        super::scaffold(&root, 7).unwrap();
+        let module = fs::read_to_string(root.join("src/days/day07.rs")).unwrap();
+        assert_eq!("let input = real(7);\n", module);
+        assert!(root.join("src/input/07.txt").exists());
+
+        // running it again must not touch the module
+        fs::write(root.join("src/days/day07.rs"), "// work in progress").unwrap();
+        let days = fs::read_to_string(root.join("src/days.rs")).unwrap();
+        super::scaffold(&root, 7).unwrap();
+        let module = fs::read_to_string(root.join("src/days/day07.rs")).unwrap();
+        assert_eq!("// work in progress", module);
+        assert_eq!(days, fs::read_to_string(root.join("src/days.rs")).unwrap());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
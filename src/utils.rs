@@ -3,7 +3,7 @@ pub mod grid {
     use std::{
         collections::HashMap,
         fmt::Display,
-        ops::{Add, Mul, Neg, Sub},
+        ops::{Add, Index, IndexMut, Mul, Neg, Sub},
     };
 
     use itertools::Itertools;
@@ -25,6 +25,10 @@ pub mod grid {
         pub fn zero() -> Self {
             Point2 { x: 0, y: 0 }
         }
+
+        pub fn manhattan(&self, other: &Point2) -> isize {
+            (self.x - other.x).abs() + (self.y - other.y).abs()
+        }
     }
 
     impl Add<Point2> for Point2 {
@@ -98,6 +102,48 @@ pub mod grid {
         }
     }
 
+    impl Sub<Point3> for Point3 {
+        type Output = Point3;
+
+        fn sub(self, rhs: Point3) -> Self::Output {
+            Point3 {
+                x: self.x - rhs.x,
+                y: self.y - rhs.y,
+                z: self.z - rhs.z,
+            }
+        }
+    }
+
+    impl Neg for Point3 {
+        type Output = Point3;
+
+        fn neg(self) -> Self::Output {
+            Point3 {
+                x: -self.x,
+                y: -self.y,
+                z: -self.z,
+            }
+        }
+    }
+
+    impl Mul<isize> for Point3 {
+        type Output = Point3;
+
+        fn mul(self, rhs: isize) -> Self::Output {
+            Point3 {
+                x: self.x * rhs,
+                y: self.y * rhs,
+                z: self.z * rhs,
+            }
+        }
+    }
+
+    impl Point3 {
+        pub fn dot(&self, other: &Point3) -> isize {
+            self.x * other.x + self.y * other.y + self.z * other.z
+        }
+    }
+
     #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
     pub struct BoundingBox2 {
         pub lowest: Point2,
@@ -231,7 +277,7 @@ pub mod grid {
         }
     }
 
-    #[derive(Clone, Copy, Debug)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
     pub enum Direction {
         North,
         NorthEast,
@@ -336,6 +382,85 @@ pub mod grid {
             }
         }
 
+        /// Parses a grid with one tile per character, leaving out the characters `tile` maps to
+        /// `None`.
+        pub fn parse<E, F>(input: &str, tile: F) -> Result<Self, GridParseError<E>>
+        where
+            F: FnMut(char) -> Result<Option<V>, E>,
+        {
+            Self::parse_at(input, Point2::zero(), tile)
+        }
+
+        /// Like [`Grid::parse`], but with the first character of the input at `origin`.
+        pub fn parse_at<E, F>(
+            input: &str,
+            origin: Point2,
+            tile: F,
+        ) -> Result<Self, GridParseError<E>>
+        where
+            F: FnMut(char) -> Result<Option<V>, E>,
+        {
+            Self::parse_inner(input, origin, &[], tile).map(|(grid, _)| grid)
+        }
+
+        /// Like [`Grid::parse`], but also returns where each of the `marks` was found. The marks
+        /// are passed to `tile` like any other character.
+        pub fn parse_marked<E, F>(
+            input: &str,
+            marks: &[char],
+            tile: F,
+        ) -> Result<(Self, HashMap<char, Point2>), GridParseError<E>>
+        where
+            F: FnMut(char) -> Result<Option<V>, E>,
+        {
+            Self::parse_inner(input, Point2::zero(), marks, tile)
+        }
+
+        fn parse_inner<E, F>(
+            input: &str,
+            origin: Point2,
+            marks: &[char],
+            mut tile: F,
+        ) -> Result<(Self, HashMap<char, Point2>), GridParseError<E>>
+        where
+            F: FnMut(char) -> Result<Option<V>, E>,
+        {
+            let mut grid = Self::new();
+            let mut marked = HashMap::new();
+
+            for (y, line) in input.lines().enumerate() {
+                grid.height = y + 1;
+                for (x, c) in line.chars().enumerate() {
+                    let p = origin
+                        + Point2 {
+                            x: x as isize,
+                            y: y as isize,
+                        };
+                    if marks.contains(&c) {
+                        marked.insert(c, p);
+                    }
+
+                    match tile(c) {
+                        Ok(Some(v)) => {
+                            grid.width = grid.width.max(x + 1);
+                            grid.map.insert(p, v);
+                        }
+                        Ok(None) => {}
+                        Err(error) => {
+                            return Err(GridParseError {
+                                line: y + 1,
+                                column: x + 1,
+                                found: c,
+                                error,
+                            })
+                        }
+                    }
+                }
+            }
+
+            Ok((grid, marked))
+        }
+
         pub fn row(&self, y: isize) -> Vec<(Point2, &V)> {
             self.map
                 .keys()
@@ -358,10 +483,9 @@ pub mod grid {
             &'a self,
             p: Point2,
             dir: Direction,
-        ) -> impl Iterator<Item = &V> + 'a {
+        ) -> impl Iterator<Item = &'a V> + 'a {
             let offset = dir.offset();
             (0..)
-                .into_iter()
                 .map(move |n| p + (offset * n))
                 .map(|coord| self.map.get(&coord))
                 .take_while(|x| x.is_some())
@@ -369,6 +493,27 @@ pub mod grid {
         }
     }
 
+    /// A character in the input that the tile mapping rejected. Lines and columns start at 1.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct GridParseError<E> {
+        pub line: usize,
+        pub column: usize,
+        pub found: char,
+        pub error: E,
+    }
+
+    impl<E: Display> Display for GridParseError<E> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(
+                f,
+                "line {}, column {}: found {:?}, expected {}",
+                self.line, self.column, self.found, self.error
+            )
+        }
+    }
+
+    impl<E: Display + std::fmt::Debug> std::error::Error for GridParseError<E> {}
+
     impl<V: Display> Display for Grid<V> {
         fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
             let map = &self.map;
@@ -383,7 +528,7 @@ pub mod grid {
             let max_y = keys.iter().max_by_key(|f| f.y).map(|f| f.y).unwrap() + 1;
             let max_x = keys.iter().max_by_key(|f| f.x).map(|f| f.x).unwrap() + 1;
             let longest_v = map.values().map(|v| v.to_string().len()).max().unwrap();
-            let empty = core::iter::repeat(' ').take(longest_v).collect::<String>();
+            let empty = std::iter::repeat_n(' ', longest_v).collect::<String>();
 
             for y in min_y..max_y {
                 let mut out = String::new();
@@ -392,7 +537,7 @@ pub mod grid {
                     .collect();
 
                 for el in row.iter() {
-                    let str = if let Some(s) = el { &s } else { &empty };
+                    let str = if let Some(s) = el { s } else { &empty };
                     out.push_str(format!("{:>width$} ", str, width = longest_v).as_ref());
                 }
 
@@ -403,6 +548,205 @@ pub mod grid {
         }
     }
 
+    /// A rectangular grid stored row by row, with a value for every point from `(0, 0)` up to
+    /// `(width - 1, height - 1)`.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct DenseGrid<V> {
+        cells: Vec<V>,
+        pub width: usize,
+        pub height: usize,
+    }
+
+    impl<V> DenseGrid<V> {
+        pub fn new(width: usize, height: usize, value: V) -> Self
+        where
+            V: Clone,
+        {
+            Self {
+                cells: vec![value; width * height],
+                width,
+                height,
+            }
+        }
+
+        /// Panics if the rows are not all the same length.
+        pub fn from_vec(vec: Vec<Vec<V>>) -> Self {
+            let height = vec.len();
+            let width = vec.first().map_or(0, |r| r.len());
+            assert!(
+                vec.iter().all(|r| r.len() == width),
+                "rows of a dense grid must all be the same length"
+            );
+
+            Self {
+                cells: vec.into_iter().flatten().collect(),
+                width,
+                height,
+            }
+        }
+
+        fn index_of(&self, p: Point2) -> Option<usize> {
+            if self.contains(p) {
+                Some(p.y as usize * self.width + p.x as usize)
+            } else {
+                None
+            }
+        }
+
+        pub fn contains(&self, p: Point2) -> bool {
+            (0..self.width as isize).contains(&p.x) && (0..self.height as isize).contains(&p.y)
+        }
+
+        pub fn get(&self, p: Point2) -> Option<&V> {
+            self.index_of(p).map(|i| &self.cells[i])
+        }
+
+        pub fn get_mut(&mut self, p: Point2) -> Option<&mut V> {
+            self.index_of(p).map(|i| &mut self.cells[i])
+        }
+
+        pub fn row(&self, y: usize) -> &[V] {
+            &self.cells[y * self.width..(y + 1) * self.width]
+        }
+
+        pub fn row_mut(&mut self, y: usize) -> &mut [V] {
+            &mut self.cells[y * self.width..(y + 1) * self.width]
+        }
+
+        pub fn col(&self, x: usize) -> impl Iterator<Item = &V> {
+            self.cells.iter().skip(x).step_by(self.width.max(1))
+        }
+
+        pub fn points(&self) -> impl Iterator<Item = Point2> {
+            let width = self.width;
+            (0..self.cells.len()).map(move |i| Point2 {
+                x: (i % width) as isize,
+                y: (i / width) as isize,
+            })
+        }
+
+        pub fn iter(&self) -> impl Iterator<Item = (Point2, &V)> {
+            self.points().zip(self.cells.iter())
+        }
+
+        pub fn values(&self) -> impl Iterator<Item = &V> {
+            self.cells.iter()
+        }
+
+        pub fn find<P>(&self, predicate: P) -> Option<(Point2, &V)>
+        where
+            P: Fn(&V) -> bool,
+        {
+            self.iter().find(|(_, v)| predicate(v))
+        }
+
+        /// The neighbours of `p` in the given directions that lie within the grid.
+        pub fn neighbours<'a>(
+            &'a self,
+            p: Point2,
+            directions: impl IntoIterator<Item = Direction> + 'a,
+        ) -> impl Iterator<Item = (Point2, &'a V)> + 'a {
+            directions
+                .into_iter()
+                .map(move |d| p + d.offset())
+                .filter_map(|n| self.get(n).map(|v| (n, v)))
+        }
+
+        pub fn line_starting_from(&self, p: Point2, dir: Direction) -> impl Iterator<Item = &V> {
+            let offset = dir.offset();
+            (0..).map_while(move |n| self.get(p + offset * n))
+        }
+
+        pub fn map<U>(self, f: impl FnMut(V) -> U) -> DenseGrid<U> {
+            DenseGrid {
+                cells: self.cells.into_iter().map(f).collect(),
+                width: self.width,
+                height: self.height,
+            }
+        }
+    }
+
+    impl<V> DenseGrid<Option<V>> {
+        /// Unwraps every cell, or returns `None` if any of them is empty.
+        pub fn filled(self) -> Option<DenseGrid<V>> {
+            Some(DenseGrid {
+                cells: self.cells.into_iter().collect::<Option<_>>()?,
+                width: self.width,
+                height: self.height,
+            })
+        }
+    }
+
+    impl<V> Index<Point2> for DenseGrid<V> {
+        type Output = V;
+
+        fn index(&self, p: Point2) -> &Self::Output {
+            self.get(p)
+                .unwrap_or_else(|| panic!("{p:?} is outside of the grid"))
+        }
+    }
+
+    impl<V> IndexMut<Point2> for DenseGrid<V> {
+        fn index_mut(&mut self, p: Point2) -> &mut Self::Output {
+            self.get_mut(p)
+                .unwrap_or_else(|| panic!("{p:?} is outside of the grid"))
+        }
+    }
+
+    /// Points that are missing in the sparse grid become `None`. Panics if it has points with
+    /// negative coordinates.
+    impl<V> From<Grid<V>> for DenseGrid<Option<V>> {
+        fn from(grid: Grid<V>) -> Self {
+            let width = grid
+                .map
+                .keys()
+                .map(|p| p.x + 1)
+                .fold(grid.width as isize, isize::max);
+            let height = grid
+                .map
+                .keys()
+                .map(|p| p.y + 1)
+                .fold(grid.height as isize, isize::max);
+            let mut dense = DenseGrid {
+                cells: (0..width * height).map(|_| None).collect(),
+                width: width as usize,
+                height: height as usize,
+            };
+            for (p, v) in grid.map {
+                assert!(
+                    p.x >= 0 && p.y >= 0,
+                    "{p:?} can't be stored in a dense grid"
+                );
+                dense[p] = Some(v);
+            }
+            dense
+        }
+    }
+
+    impl<V> From<DenseGrid<V>> for Grid<V> {
+        fn from(dense: DenseGrid<V>) -> Self {
+            let (width, height) = (dense.width, dense.height);
+            let points = dense.points().collect::<Vec<_>>();
+            Grid {
+                map: points.into_iter().zip(dense.cells).collect(),
+                width,
+                height,
+            }
+        }
+    }
+
+    impl<V: Display> Display for DenseGrid<V> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            for y in 0..self.height {
+                for v in self.row(y) {
+                    write!(f, "{v}")?;
+                }
+                writeln!(f)?;
+            }
+            Ok(())
+        }
+    }
+
     #[cfg(test)]
     mod tests {
         use super::*;
@@ -428,5 +772,982 @@ pub mod grid {
             .map(|(x, y)| Point2 { x, y });
             assert_eq!(&expected, &points[..]);
         }
+
+        #[test]
+        fn parse() {
+            let tile = |c| match c {
+                '#' => Ok(Some(true)),
+                '.' | 'S' => Ok(Some(false)),
+                ' ' => Ok(None),
+                _ => Err("`#`, `.`, `S` or ` `"),
+            };
+
+            let (grid, marks) = Grid::parse_marked("  #.\r\n#S.\r\n ", &['S'], tile).unwrap();
+            assert_eq!((4, 3), (grid.width, grid.height));
+            assert_eq!(5, grid.map.len());
+            assert_eq!(Some(&true), grid.map.get(&Point2 { x: 2, y: 0 }));
+            assert_eq!(Some(&Point2 { x: 1, y: 1 }), marks.get(&'S'));
+
+            let grid = Grid::parse_at("#", Point2 { x: -1, y: 3 }, tile).unwrap();
+            assert_eq!(
+                vec![Point2 { x: -1, y: 3 }],
+                grid.map.into_keys().collect::<Vec<_>>()
+            );
+
+            let err = Grid::parse("#.\n.x", tile).unwrap_err();
+            assert_eq!(
+                "line 2, column 2: found 'x', expected `#`, `.`, `S` or ` `",
+                err.to_string()
+            );
+        }
+
+        #[test]
+        fn dense_grid() {
+            let grid = DenseGrid::from_vec(vec![vec![1, 2, 3], vec![4, 5, 6]]);
+            assert_eq!(5, grid[Point2 { x: 1, y: 1 }]);
+            assert_eq!(None, grid.get(Point2 { x: 3, y: 0 }));
+            assert_eq!(&[4, 5, 6], grid.row(1));
+            assert_eq!(vec![&3, &6], grid.col(2).collect::<Vec<_>>());
+
+            let neighbours = grid
+                .neighbours(Point2 { x: 0, y: 0 }, Direction::cardinals())
+                .map(|(_, v)| *v)
+                .collect::<Vec<_>>();
+            assert_eq!(vec![2, 4], neighbours);
+
+            let line = grid
+                .line_starting_from(Point2 { x: 2, y: 1 }, Direction::West)
+                .copied()
+                .collect::<Vec<_>>();
+            assert_eq!(vec![6, 5, 4], line);
+        }
+
+        #[test]
+        fn dense_sparse_conversion() {
+            let sparse = Grid::from_vec(vec![vec![Some('a'), None], vec![Some('b'), Some('c')]]);
+            let dense = DenseGrid::from(sparse.clone());
+            assert_eq!(
+                DenseGrid::from_vec(vec![vec![Some('a'), None], vec![Some('b'), Some('c')]]),
+                dense
+            );
+            assert_eq!(None, dense.clone().filled());
+
+            let back = Grid::from(dense)
+                .map
+                .into_iter()
+                .filter_map(|(p, v)| Some((p, v?)));
+            assert_eq!(sparse.map, back.collect());
+        }
+    }
+}
+
+#[allow(dead_code)]
+pub mod render {
+    use std::{
+        collections::HashMap,
+        fs::File,
+        io::{self, BufWriter, Write},
+        path::Path,
+    };
+
+    use super::grid::{BoundingBox2, DenseGrid, Grid, Point2};
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub struct Rgb(pub u8, pub u8, pub u8);
+
+    impl Rgb {
+        pub const BLACK: Rgb = Rgb(0, 0, 0);
+        pub const WHITE: Rgb = Rgb(255, 255, 255);
+    }
+
+    /// How a grid value is drawn.
+    pub trait ToRgb {
+        fn to_rgb(&self) -> Rgb;
+    }
+
+    impl ToRgb for Rgb {
+        fn to_rgb(&self) -> Rgb {
+            *self
+        }
+    }
+
+    impl ToRgb for bool {
+        fn to_rgb(&self) -> Rgb {
+            if *self {
+                Rgb::WHITE
+            } else {
+                Rgb::BLACK
+            }
+        }
+    }
+
+    impl ToRgb for () {
+        fn to_rgb(&self) -> Rgb {
+            Rgb::WHITE
+        }
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    pub struct RenderOptions {
+        /// Width and height in pixels of a single grid point.
+        pub scale: usize,
+        /// The part of the grid to draw. Defaults to everything in the grid.
+        pub viewport: Option<BoundingBox2>,
+        /// The colour of points that are not in the grid.
+        pub background: Rgb,
+    }
+
+    impl Default for RenderOptions {
+        fn default() -> Self {
+            Self {
+                scale: 1,
+                viewport: None,
+                background: Rgb::BLACK,
+            }
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Image {
+        pub width: usize,
+        pub height: usize,
+        pub pixels: Vec<Rgb>,
+    }
+
+    impl Image {
+        pub fn render<V: ToRgb>(grid: &Grid<V>, options: &RenderOptions) -> Self {
+            let viewport = options
+                .viewport
+                .unwrap_or_else(|| BoundingBox2::from_points(grid.map.keys().copied()));
+            Self::from_fn(viewport, options, |p| grid.map.get(&p).map(ToRgb::to_rgb))
+        }
+
+        pub fn render_dense<V: ToRgb>(grid: &DenseGrid<V>, options: &RenderOptions) -> Self {
+            let viewport = options.viewport.unwrap_or(BoundingBox2 {
+                lowest: Point2::zero(),
+                highest: Point2 {
+                    x: grid.width as isize - 1,
+                    y: grid.height as isize - 1,
+                },
+            });
+            Self::from_fn(viewport, options, |p| grid.get(p).map(ToRgb::to_rgb))
+        }
+
+        fn from_fn<F>(viewport: BoundingBox2, options: &RenderOptions, colour: F) -> Self
+        where
+            F: Fn(Point2) -> Option<Rgb>,
+        {
+            // an empty grid has an inverted bounding box
+            if viewport.lowest.x > viewport.highest.x || viewport.lowest.y > viewport.highest.y {
+                return Image {
+                    width: 0,
+                    height: 0,
+                    pixels: Vec::new(),
+                };
+            }
+
+            let scale = options.scale.max(1);
+            let columns = viewport.lowest.x.abs_diff(viewport.highest.x) + 1;
+            let (width, height) = (columns * scale, (viewport.area() / columns) * scale);
+
+            let mut pixels = Vec::with_capacity(width * height);
+            for p in viewport {
+                let rgb = colour(p).unwrap_or(options.background);
+                pixels.extend(std::iter::repeat_n(rgb, scale));
+                // repeat a finished row of pixels for the rest of the scaled row
+                if p.x == viewport.highest.x {
+                    let row = pixels.len() - width;
+                    for _ in 1..scale {
+                        pixels.extend_from_within(row..row + width);
+                    }
+                }
+            }
+
+            Image {
+                width,
+                height,
+                pixels,
+            }
+        }
+
+        /// Writes a binary (P6) PPM image.
+        pub fn write_ppm(&self, mut w: impl Write) -> io::Result<()> {
+            write!(w, "P6\n{} {}\n255\n", self.width, self.height)?;
+            let bytes = self
+                .pixels
+                .iter()
+                .flat_map(|Rgb(r, g, b)| [*r, *g, *b])
+                .collect::<Vec<_>>();
+            w.write_all(&bytes)
+        }
+
+        #[cfg(feature = "png")]
+        pub fn write_png(&self, w: impl Write) -> io::Result<()> {
+            // PNG stores sizes in 4 bytes, but only allows up to 2^31 - 1
+            let size = |n: usize| {
+                u32::try_from(n)
+                    .ok()
+                    .filter(|n| *n <= i32::MAX as u32)
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("PNG images can't be larger than 2^31 - 1 pixels, not {}", n),
+                        )
+                    })
+            };
+            let mut encoder = png::Encoder::new(w, size(self.width)?, size(self.height)?);
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.set_depth(png::BitDepth::Eight);
+            let bytes = self
+                .pixels
+                .iter()
+                .flat_map(|Rgb(r, g, b)| [*r, *g, *b])
+                .collect::<Vec<_>>();
+            encoder
+                .write_header()
+                .and_then(|mut writer| writer.write_image_data(&bytes))
+                .map_err(io::Error::other)
+        }
+
+        /// Saves the image in the format matching the file extension, `.ppm` or `.png`. PNG
+        /// support needs the `png` feature.
+        pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+            let path = path.as_ref();
+            let file = BufWriter::new(File::create(path)?);
+            match path.extension().and_then(|e| e.to_str()) {
+                Some("ppm") => self.write_ppm(file),
+                #[cfg(feature = "png")]
+                Some("png") => self.write_png(file),
+                _ => Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("can't save {} as an image", path.display()),
+                )),
+            }
+        }
+
+        /// The image as ANSI truecolour escape codes, with two rows of pixels per line of text.
+        pub fn to_ansi(&self) -> String {
+            let mut out = String::new();
+            for y in (0..self.height).step_by(2) {
+                for x in 0..self.width {
+                    let Rgb(r, g, b) = self.pixels[y * self.width + x];
+                    out.push_str(&format!("\x1b[38;2;{};{};{}m", r, g, b));
+                    if y + 1 < self.height {
+                        let Rgb(r, g, b) = self.pixels[(y + 1) * self.width + x];
+                        out.push_str(&format!("\x1b[48;2;{};{};{}m", r, g, b));
+                    }
+                    out.push('▀');
+                }
+                out.push_str("\x1b[0m\n");
+            }
+            out
+        }
+    }
+
+    /// Collects snapshots of a simulation, one per step, to write out as an animation.
+    ///
+    /// Simulations take a `&mut Recorder` and call [`Recorder::record_with`] after every step.
+    /// Snapshots are only built for the steps that are kept, so a recorder made with
+    /// [`Recorder::off`] costs next to nothing. Simulations that change a few cells per step
+    /// can call [`Recorder::record_changes`] instead and skip building snapshots altogether.
+    ///
+    /// Frames only store the cells that changed since the frame before, and are rendered one at
+    /// a time when written out, so long runs don't hold a copy of the whole grid per step.
+    #[derive(Clone, Debug)]
+    pub struct Recorder {
+        enabled: bool,
+        every: usize,
+        delay: u16,
+        steps: usize,
+        /// The cells that changed since the previous frame. `None` clears a cell.
+        frames: Vec<Vec<(Point2, Option<Rgb>)>>,
+        /// What the latest step looks like.
+        current: Grid<Rgb>,
+        /// The changes since the last frame that [`Recorder::record_changes`] hasn't kept yet.
+        pending: HashMap<Point2, Option<Rgb>>,
+    }
+
+    impl Recorder {
+        pub fn new() -> Self {
+            Self {
+                enabled: true,
+                every: 1,
+                delay: 10,
+                steps: 0,
+                frames: Vec::new(),
+                current: Grid::new(),
+                pending: HashMap::new(),
+            }
+        }
+
+        /// A recorder that ignores every step.
+        pub fn off() -> Self {
+            Self {
+                enabled: false,
+                ..Self::new()
+            }
+        }
+
+        /// Only keeps every `n`th step, starting with the first.
+        pub fn every(mut self, n: usize) -> Self {
+            self.every = n.max(1);
+            self
+        }
+
+        /// How long each frame of a GIF is shown, in hundredths of a second.
+        pub fn delay(mut self, delay: u16) -> Self {
+            self.delay = delay;
+            self
+        }
+
+        pub fn record<V: ToRgb>(&mut self, grid: &Grid<V>) {
+            self.record_with(|| grid.map.iter().map(|(p, v)| (*p, v.to_rgb())));
+        }
+
+        /// Records the points and colours returned by `snapshot`, if this step is kept.
+        pub fn record_with<F, I>(&mut self, snapshot: F)
+        where
+            F: FnOnce() -> I,
+            I: IntoIterator<Item = (Point2, Rgb)>,
+        {
+            if !self.keep() {
+                return;
+            }
+            let mut next = Grid::new();
+            next.map.extend(snapshot());
+            // cells with pending changes might differ from the last frame even if they match
+            // the latest step
+            let mut changes = self
+                .pending
+                .keys()
+                .chain(self.current.map.keys())
+                .filter(|p| self.pending.contains_key(p) || !next.map.contains_key(p))
+                .map(|p| (*p, next.map.get(p).copied()))
+                .collect::<HashMap<_, _>>();
+            changes.extend(
+                next.map
+                    .iter()
+                    .filter(|(p, c)| self.current.map.get(p) != Some(c))
+                    .map(|(p, c)| (*p, Some(*c))),
+            );
+            self.frames.push(changes.into_iter().collect());
+            self.current = next;
+            self.pending.clear();
+        }
+
+        /// Records a step that only changed `changes` since the step before. `None` clears a
+        /// cell. Unlike snapshots, changes have to be passed for every step, kept or not.
+        pub fn record_changes(&mut self, changes: impl IntoIterator<Item = (Point2, Option<Rgb>)>) {
+            if !self.enabled {
+                return;
+            }
+            for (p, colour) in changes {
+                match colour {
+                    Some(c) => self.current.map.insert(p, c),
+                    None => self.current.map.remove(&p),
+                };
+                self.pending.insert(p, colour);
+            }
+            if self.keep() {
+                self.frames.push(self.pending.drain().collect());
+            }
+        }
+
+        /// Whether the step being recorded is kept, counting it.
+        fn keep(&mut self) -> bool {
+            let keep = self.enabled && self.steps.is_multiple_of(self.every);
+            self.steps += 1;
+            keep
+        }
+
+        pub fn frame_count(&self) -> usize {
+            self.frames.len()
+        }
+
+        /// Renders the frames one at a time over the same area: the options' viewport, or else
+        /// the smallest box around every frame.
+        pub fn images<'a>(&'a self, options: &RenderOptions) -> impl Iterator<Item = Image> + 'a {
+            let viewport = options.viewport.unwrap_or_else(|| {
+                BoundingBox2::from_points(
+                    self.frames
+                        .iter()
+                        .flatten()
+                        .filter_map(|(p, c)| c.map(|_| *p)),
+                )
+            });
+            let options = RenderOptions {
+                viewport: Some(viewport),
+                ..*options
+            };
+            self.frames.iter().scan(Grid::new(), move |grid, changes| {
+                for (p, colour) in changes {
+                    match colour {
+                        Some(c) => grid.map.insert(*p, *c),
+                        None => grid.map.remove(p),
+                    };
+                }
+                Some(Image::render(grid, &options))
+            })
+        }
+
+        /// Writes the frames into `dir` as `frame_00000.ppm`, `frame_00001.ppm`, …
+        pub fn write_ppm_sequence(
+            &self,
+            dir: impl AsRef<Path>,
+            options: &RenderOptions,
+        ) -> io::Result<()> {
+            let dir = dir.as_ref();
+            std::fs::create_dir_all(dir)?;
+            for (i, image) in self.images(options).enumerate() {
+                image.save(dir.join(format!("frame_{:05}.ppm", i)))?;
+            }
+            Ok(())
+        }
+
+        /// Writes the frames as an endlessly looping GIF.
+        #[cfg(feature = "gif")]
+        pub fn write_gif(&self, w: impl Write, options: &RenderOptions) -> io::Result<()> {
+            let mut images = self.images(options).peekable();
+            let Some(first) = images.peek() else {
+                return Ok(());
+            };
+            let size = |n: usize| {
+                u16::try_from(n).map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("GIF frames can't be larger than 65535 pixels, not {}", n),
+                    )
+                })
+            };
+            let (width, height) = (size(first.width)?, size(first.height)?);
+            let mut encoder = gif::Encoder::new(w, width, height, &[]).map_err(io::Error::other)?;
+            encoder
+                .set_repeat(gif::Repeat::Infinite)
+                .map_err(io::Error::other)?;
+            for image in images {
+                let bytes = image
+                    .pixels
+                    .iter()
+                    .flat_map(|Rgb(r, g, b)| [*r, *g, *b])
+                    .collect::<Vec<_>>();
+                let mut frame = gif::Frame::from_rgb_speed(width, height, &bytes, 10);
+                frame.delay = self.delay;
+                encoder.write_frame(&frame).map_err(io::Error::other)?;
+            }
+            Ok(())
+        }
+
+        /// Saves the frames as a GIF if `path` ends in `.gif`, which needs the `gif` feature, or
+        /// else as a PPM sequence in the directory `path`.
+        pub fn save(&self, path: impl AsRef<Path>, options: &RenderOptions) -> io::Result<()> {
+            let path = path.as_ref();
+            match path.extension().and_then(|e| e.to_str()) {
+                #[cfg(feature = "gif")]
+                Some("gif") => self.write_gif(BufWriter::new(File::create(path)?), options),
+                #[cfg(not(feature = "gif"))]
+                Some("gif") => Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "saving a GIF needs the `gif` feature",
+                )),
+                _ => self.write_ppm_sequence(path, options),
+            }
+        }
+    }
+
+    impl Default for Recorder {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn grid() -> Grid<Rgb> {
+            let red = Rgb(255, 0, 0);
+            Grid::from_vec(vec![vec![Some(red), None], vec![None, Some(Rgb::WHITE)]])
+        }
+
+        #[test]
+        fn render_scaled() {
+            let options = RenderOptions {
+                scale: 2,
+                ..Default::default()
+            };
+            let image = Image::render(&grid(), &options);
+            assert_eq!((4, 4), (image.width, image.height));
+            let red = Rgb(255, 0, 0);
+            let (b, w) = (Rgb::BLACK, Rgb::WHITE);
+            #[rustfmt::skip]
+            let expected = vec![
+                red, red, b, b,
+                red, red, b, b,
+                b, b, w, w,
+                b, b, w, w,
+            ];
+            assert_eq!(expected, image.pixels);
+        }
+
+        #[test]
+        fn render_viewport() {
+            let options = RenderOptions {
+                viewport: Some(BoundingBox2 {
+                    lowest: Point2 { x: 1, y: 1 },
+                    highest: Point2 { x: 2, y: 1 },
+                }),
+                background: Rgb(1, 2, 3),
+                ..Default::default()
+            };
+            let image = Image::render(&grid(), &options);
+            assert_eq!(vec![Rgb::WHITE, Rgb(1, 2, 3)], image.pixels);
+        }
+
+        #[test]
+        fn ppm() {
+            let image = Image::render(&grid(), &RenderOptions::default());
+            let mut out = Vec::new();
+            image.write_ppm(&mut out).unwrap();
+            assert_eq!(b"P6\n2 2\n255\n", &out[..11]);
+            assert_eq!(&[255, 0, 0, 0, 0, 0], &out[11..17]);
+            assert_eq!(11 + 2 * 2 * 3, out.len());
+        }
+
+        #[cfg(feature = "png")]
+        #[test]
+        fn png() {
+            let image = Image::render(&grid(), &RenderOptions::default());
+            let mut out = Vec::new();
+            image.write_png(&mut out).unwrap();
+            assert_eq!(b"\x89PNG", &out[..4]);
+
+            // the size is checked before any pixels are written
+            let image = Image {
+                width: 1 << 31,
+                height: 1,
+                pixels: Vec::new(),
+            };
+            let err = image.write_png(Vec::new()).unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+        }
+
+        #[test]
+        fn recorder() {
+            let mut recorder = Recorder::new().every(2);
+            for x in 0..5 {
+                recorder.record_with(|| [(Point2 { x, y: 0 }, Rgb::WHITE)]);
+            }
+            assert_eq!(3, recorder.frame_count());
+
+            // every frame covers the points of all of them
+            let images = recorder
+                .images(&RenderOptions::default())
+                .collect::<Vec<_>>();
+            assert!(images.iter().all(|i| (i.width, i.height) == (5, 1)));
+            let (b, w) = (Rgb::BLACK, Rgb::WHITE);
+            assert_eq!(vec![b, b, w, b, b], images[1].pixels);
+
+            let mut recorder = Recorder::off();
+            recorder.record(&grid());
+            recorder.record_changes([(Point2 { x: 0, y: 0 }, None)]);
+            assert_eq!(0, recorder.frame_count());
+        }
+
+        #[test]
+        fn record_changes() {
+            let red = Rgb(255, 0, 0);
+            let (b, w) = (Rgb::BLACK, Rgb::WHITE);
+            let mut recorder = Recorder::new().every(2);
+            recorder.record(&grid());
+            // the changes of skipped steps show up in the next frame that is kept
+            recorder.record_changes([(Point2 { x: 0, y: 0 }, None)]);
+            recorder.record_changes([(Point2 { x: 1, y: 0 }, Some(red))]);
+            recorder.record_changes([(Point2 { x: 1, y: 1 }, None)]);
+            recorder.record_with(|| [(Point2 { x: 0, y: 1 }, w)]);
+            assert_eq!(3, recorder.frame_count());
+
+            let images = recorder
+                .images(&RenderOptions::default())
+                .collect::<Vec<_>>();
+            let pixels = images.iter().map(|i| i.pixels.clone()).collect::<Vec<_>>();
+            let expected = vec![vec![red, b, b, w], vec![b, red, b, w], vec![b, b, w, b]];
+            assert_eq!(expected, pixels);
+        }
+
+        #[cfg(feature = "gif")]
+        #[test]
+        fn gif() {
+            let mut recorder = Recorder::new();
+            recorder.record(&grid());
+            recorder.record(&grid());
+            let mut out = Vec::new();
+            recorder
+                .write_gif(&mut out, &RenderOptions::default())
+                .unwrap();
+            assert_eq!(b"GIF89a", &out[..6]);
+        }
+
+        #[cfg(feature = "gif")]
+        #[test]
+        fn gif_too_large() {
+            let mut recorder = Recorder::new();
+            recorder.record_with(|| [0, 70_000].map(|x| (Point2 { x, y: 0 }, Rgb::WHITE)));
+            let err = recorder
+                .write_gif(Vec::new(), &RenderOptions::default())
+                .unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+        }
+
+        #[test]
+        fn ansi() {
+            let image = Image::render(&grid(), &RenderOptions::default());
+            let expected = "\x1b[38;2;255;0;0m\x1b[48;2;0;0;0m▀\
+                            \x1b[38;2;0;0;0m\x1b[48;2;255;255;255m▀\x1b[0m\n";
+            assert_eq!(expected, image.to_ansi());
+        }
+    }
+}
+
+#[allow(dead_code)]
+pub mod automaton {
+    use std::collections::HashMap;
+
+    use super::grid::{BoundingBox2, Direction, Point2};
+
+    /// The neighbouring points a rule looks at before proposing a move.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Neighbourhood {
+        /// The rule doesn't look at its neighbours.
+        None,
+        Cardinal,
+        Ordinal,
+        All,
+    }
+
+    impl Neighbourhood {
+        pub fn directions(&self) -> &'static [Direction] {
+            const CARDINAL: [Direction; 4] = [
+                Direction::North,
+                Direction::East,
+                Direction::South,
+                Direction::West,
+            ];
+            const ORDINAL: [Direction; 4] = [
+                Direction::NorthEast,
+                Direction::SouthEast,
+                Direction::SouthWest,
+                Direction::NorthWest,
+            ];
+            const ALL: [Direction; 8] = [
+                Direction::North,
+                Direction::NorthEast,
+                Direction::East,
+                Direction::SouthEast,
+                Direction::South,
+                Direction::SouthWest,
+                Direction::West,
+                Direction::NorthWest,
+            ];
+            match self {
+                Neighbourhood::None => &[],
+                Neighbourhood::Cardinal => &CARDINAL,
+                Neighbourhood::Ordinal => &ORDINAL,
+                Neighbourhood::All => &ALL,
+            }
+        }
+    }
+
+    /// Which of the neighbours in a rule's [`Neighbourhood`] are occupied.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct Neighbours(u8);
+
+    impl Neighbours {
+        pub fn is_empty(&self) -> bool {
+            self.0 == 0
+        }
+
+        pub fn occupied(&self, dir: Direction) -> bool {
+            self.0 & (1 << dir as u8) != 0
+        }
+
+        pub fn count(&self) -> u32 {
+            self.0.count_ones()
+        }
+
+        fn insert(&mut self, dir: Direction) {
+            self.0 |= 1 << dir as u8;
+        }
+    }
+
+    /// What happens to moves that leave the area.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Boundary {
+        Unbounded,
+        /// Moves out of the box are refused, and the entity stays where it is.
+        Walled(BoundingBox2),
+        /// Moves out of one side of the box come back in on the opposite side.
+        Wrapping(BoundingBox2),
+    }
+
+    impl Boundary {
+        fn apply(&self, p: Point2) -> Option<Point2> {
+            match self {
+                Boundary::Unbounded => Some(p),
+                Boundary::Walled(bb) => bb.contains(&p).then_some(p),
+                Boundary::Wrapping(bb) => {
+                    let wrap = |v: isize, lowest: isize, highest: isize| {
+                        lowest + (v - lowest).rem_euclid(highest - lowest + 1)
+                    };
+                    Some(Point2 {
+                        x: wrap(p.x, bb.lowest.x, bb.highest.x),
+                        y: wrap(p.y, bb.lowest.y, bb.highest.y),
+                    })
+                }
+            }
+        }
+    }
+
+    /// What happens when several entities propose moving to the same point.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Conflict {
+        /// None of them move.
+        Cancel,
+        /// They all move, and share the point.
+        Stack,
+    }
+
+    /// How the entities of an [`Automaton`] move in a single step.
+    pub trait Rules {
+        type Entity;
+
+        fn neighbourhood(&self) -> Neighbourhood;
+        fn boundary(&self) -> Boundary;
+        fn conflict(&self) -> Conflict;
+
+        /// The direction `entity` at `point` wants to move in, or `None` to stay put.
+        fn propose(
+            &self,
+            point: Point2,
+            entity: &Self::Entity,
+            neighbours: Neighbours,
+        ) -> Option<Direction>;
+
+        /// Called after every step, for rules that change over time.
+        fn end_step(&mut self) {}
+    }
+
+    /// Entities on a grid that all move at the same time, following some [`Rules`].
+    pub struct Automaton<R: Rules> {
+        pub rules: R,
+        pub cells: HashMap<Point2, Vec<R::Entity>>,
+    }
+
+    impl<R: Rules> Automaton<R>
+    where
+        R::Entity: Clone,
+    {
+        pub fn new(rules: R, entities: impl IntoIterator<Item = (Point2, R::Entity)>) -> Self {
+            let mut cells: HashMap<_, Vec<_>> = HashMap::new();
+            for (p, entity) in entities {
+                cells.entry(p).or_default().push(entity);
+            }
+            Self { rules, cells }
+        }
+
+        pub fn occupied(&self, p: &Point2) -> bool {
+            self.cells.contains_key(p)
+        }
+
+        pub fn points(&self) -> impl Iterator<Item = Point2> + '_ {
+            self.cells.keys().copied()
+        }
+
+        /// Moves every entity once. Returns how many of them moved.
+        pub fn step(&mut self) -> usize {
+            let directions = self.rules.neighbourhood().directions();
+            let boundary = self.rules.boundary();
+
+            // proposal phase
+            let cells = std::mem::take(&mut self.cells);
+            let mut moves = Vec::new();
+            let mut stays = Vec::new();
+            for (point, entities) in &cells {
+                let mut neighbours = Neighbours::default();
+                for dir in directions {
+                    if cells.contains_key(&(*point + dir.offset())) {
+                        neighbours.insert(*dir);
+                    }
+                }
+                for entity in entities {
+                    let target = self
+                        .rules
+                        .propose(*point, entity, neighbours)
+                        .and_then(|dir| boundary.apply(*point + dir.offset()));
+                    match target {
+                        Some(target) if target != *point => {
+                            moves.push((*point, target, entity.clone()))
+                        }
+                        _ => stays.push((*point, entity.clone())),
+                    }
+                }
+            }
+
+            // conflict resolution
+            let mut proposals: HashMap<Point2, usize> = HashMap::new();
+            for (_, target, _) in &moves {
+                *proposals.entry(*target).or_default() += 1;
+            }
+            let conflict = self.rules.conflict();
+            let mut moved = 0;
+            for (from, target, entity) in moves {
+                if conflict == Conflict::Cancel && proposals[&target] > 1 {
+                    stays.push((from, entity));
+                } else {
+                    self.cells.entry(target).or_default().push(entity);
+                    moved += 1;
+                }
+            }
+            for (p, entity) in stays {
+                self.cells.entry(p).or_default().push(entity);
+            }
+
+            self.rules.end_step();
+            moved
+        }
+
+        /// Steps until nothing moves, or until `limit` steps have been taken. `each` sees the
+        /// automaton before every step. Returns the number of steps
+        /// taken, including the one where nothing moved.
+        pub fn run(&mut self, limit: Option<usize>, mut each: impl FnMut(&Self)) -> usize {
+            let mut steps = 0;
+            loop {
+                each(self);
+                if limit.is_some_and(|l| steps >= l) {
+                    break;
+                }
+                steps += 1;
+                if self.step() == 0 {
+                    break;
+                }
+            }
+            steps
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use itertools::Itertools;
+
+        use super::*;
+
+        /// Every entity moves in its own direction.
+        struct Drift(Boundary, Conflict);
+
+        impl Rules for Drift {
+            type Entity = Direction;
+
+            fn neighbourhood(&self) -> Neighbourhood {
+                Neighbourhood::None
+            }
+
+            fn boundary(&self) -> Boundary {
+                self.0
+            }
+
+            fn conflict(&self) -> Conflict {
+                self.1
+            }
+
+            fn propose(&self, _: Point2, dir: &Direction, _: Neighbours) -> Option<Direction> {
+                Some(*dir)
+            }
+        }
+
+        fn p(x: isize, y: isize) -> Point2 {
+            Point2 { x, y }
+        }
+
+        const BOX: BoundingBox2 = BoundingBox2 {
+            lowest: Point2 { x: 0, y: 0 },
+            highest: Point2 { x: 2, y: 2 },
+        };
+
+        #[test]
+        fn wrapping_and_stacking() {
+            let entities = [(p(2, 1), Direction::East), (p(1, 0), Direction::South)];
+            let mut automaton =
+                Automaton::new(Drift(Boundary::Wrapping(BOX), Conflict::Stack), entities);
+            assert_eq!(2, automaton.step());
+            assert_eq!(
+                vec![p(0, 1), p(1, 1)],
+                automaton.points().sorted().collect_vec()
+            );
+
+            let entities = [(p(0, 1), Direction::East), (p(2, 1), Direction::West)];
+            let mut automaton =
+                Automaton::new(Drift(Boundary::Wrapping(BOX), Conflict::Stack), entities);
+            automaton.step();
+            assert_eq!(2, automaton.cells[&p(1, 1)].len());
+        }
+
+        #[test]
+        fn walls_and_cancelled_moves() {
+            let entities = [
+                (p(0, 1), Direction::East),
+                (p(2, 1), Direction::West),
+                (p(2, 2), Direction::South),
+            ];
+            let mut automaton =
+                Automaton::new(Drift(Boundary::Walled(BOX), Conflict::Cancel), entities);
+            assert_eq!(0, automaton.step());
+            assert_eq!(
+                vec![p(0, 1), p(2, 1), p(2, 2)],
+                automaton.points().sorted().collect_vec()
+            );
+        }
+
+        /// Entities with an occupied cardinal neighbour move away from it.
+        struct Spread;
+
+        impl Rules for Spread {
+            type Entity = ();
+
+            fn neighbourhood(&self) -> Neighbourhood {
+                Neighbourhood::Cardinal
+            }
+
+            fn boundary(&self) -> Boundary {
+                Boundary::Unbounded
+            }
+
+            fn conflict(&self) -> Conflict {
+                Conflict::Cancel
+            }
+
+            fn propose(&self, _: Point2, _: &(), neighbours: Neighbours) -> Option<Direction> {
+                if neighbours.occupied(Direction::West) {
+                    Some(Direction::East)
+                } else if neighbours.occupied(Direction::East) {
+                    Some(Direction::West)
+                } else {
+                    None
+                }
+            }
+        }
+
+        #[test]
+        fn run_until_stable() {
+            let mut automaton = Automaton::new(Spread, [(p(0, 0), ()), (p(1, 0), ())]);
+            let mut seen = 0;
+            assert_eq!(2, automaton.run(None, |_| seen += 1));
+            assert_eq!(2, seen);
+            assert_eq!(
+                vec![p(-1, 0), p(2, 0)],
+                automaton.points().sorted().collect_vec()
+            );
+
+            let mut automaton = Automaton::new(Spread, [(p(0, 0), ()), (p(1, 0), ())]);
+            assert_eq!(0, automaton.run(Some(0), |_| {}));
+        }
     }
 }